@@ -0,0 +1,96 @@
+//! Compares `Stickfigure::compute_pose` against per-node `Node::get_global_end` on 400-node stickfigures, with and
+//! without connectors.
+//!
+//! Run with `cargo bench --bench pose`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use sticknodes_rs::{ConnectorData, DrawOrderIndex, Node, NodeOptions, Stickfigure};
+
+const LIMBS: usize = 21;
+const NODES_PER_LIMB: usize = 19;
+
+/// Builds a stickfigure with `LIMBS` chains of `NODES_PER_LIMB` nodes hanging off the root node (400 nodes in total).
+///
+/// With `with_connectors`, the last node of every limb but the first is a connector to the middle of the limb before it.
+fn build_stickfigure(with_connectors: bool) -> Stickfigure {
+    let mut stickfigure = Stickfigure::new();
+    let mut previous_limb_middle = None;
+
+    for limb in 0..LIMBS {
+        let mut parent = DrawOrderIndex(0);
+        let mut limb_middle = parent;
+        for segment in 0..NODES_PER_LIMB {
+            let mut options = NodeOptions::default();
+            options.length = 10.0 + segment as f32;
+            options.local_angle = (limb * 17 + segment * 5) as f32;
+            let node = Node::from_options(options);
+
+            parent = match previous_limb_middle {
+                Some(end_node) if with_connectors && segment == NODES_PER_LIMB - 1 => stickfigure
+                    .add_connector(node, parent, end_node, ConnectorData::default())
+                    .expect("connector ends at an existing node"),
+                _ => stickfigure
+                    .add_node(node, parent)
+                    .expect("400 nodes fit within the node limit"),
+            };
+            if segment == NODES_PER_LIMB / 2 {
+                limb_middle = parent;
+            }
+        }
+        previous_limb_middle = Some(limb_middle);
+    }
+
+    stickfigure
+}
+
+fn measure<F: FnMut()>(name: &str, iterations: u32, mut f: F) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let per_iteration = start.elapsed() / iterations;
+
+    println!("{name:<40} {:>12?} per iteration", per_iteration);
+}
+
+fn bench(name: &str, stickfigure: &Stickfigure) {
+    let draw_indices = stickfigure.get_all_node_indices();
+    println!("{name}: {} nodes", draw_indices.len());
+
+    measure("compute_pose", 1_000, || {
+        black_box(stickfigure.compute_pose());
+    });
+
+    measure("get_global_end for every node", 20, || {
+        for draw_index in draw_indices.iter() {
+            let node = stickfigure.get_node(*draw_index).unwrap();
+            black_box(node.get_global_end(stickfigure));
+        }
+    });
+
+    let pose = stickfigure.compute_pose();
+    measure("compute_pose + lookup for every node", 1_000, || {
+        let pose = stickfigure.compute_pose();
+        for draw_index in draw_indices.iter() {
+            black_box(pose.get(*draw_index).map(|transform| transform.end));
+        }
+    });
+
+    // Sanity check: both approaches agree.
+    let max_difference = draw_indices
+        .iter()
+        .map(|draw_index| {
+            let node = stickfigure.get_node(*draw_index).unwrap();
+            let cached = pose.get(*draw_index).unwrap().end;
+            (node.get_global_end(stickfigure) - cached).length()
+        })
+        .fold(0.0f32, f32::max);
+    assert!(max_difference < 1e-3, "pose and getters disagree by {max_difference}");
+}
+
+fn main() {
+    bench("without connectors", &build_stickfigure(false));
+    bench("with connectors", &build_stickfigure(true));
+}
@@ -0,0 +1,220 @@
+//! Command-line tool for inspecting, converting and rendering `.nodes` files.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use sticknodes_rs::{Color, RasterOptions, SerializableStickfigure, Severity, Stickfigure, SvgOptions};
+
+#[derive(Parser)]
+#[command(name = "sticknodes", version, about = "Inspect, convert and render Stick Nodes .nodes files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the version, build and node and polyfill counts of a stickfigure.
+    Info { input: PathBuf },
+    /// Report problems the app would choke on. Exits with status 1 if any is an error.
+    Validate { input: PathBuf },
+    /// Convert a stickfigure between .nodes, JSON, RON and the text form of `Stickfigure::to_text` (.txt).
+    Convert {
+        input: PathBuf,
+        output: PathBuf,
+        /// Format of the input. Guessed from its extension if not given.
+        #[arg(long)]
+        from: Option<Format>,
+        /// Format of the output. Guessed from its extension if not given.
+        #[arg(long)]
+        to: Option<Format>,
+    },
+    /// Draw a stickfigure as an SVG or PNG image, depending on the output's extension.
+    Render {
+        input: PathBuf,
+        output: PathBuf,
+        /// Width of the image in pixels.
+        #[arg(long, default_value_t = 512)]
+        width: u32,
+        /// Height of the PNG image in pixels. Follows from the figure's proportions if not given.
+        #[arg(long)]
+        height: Option<u32>,
+        /// Background color as a hex string, e.g. "#ffffff". Transparent if not given.
+        #[arg(long)]
+        background: Option<String>,
+    },
+    /// List the differences between two stickfigures. Exits with status 1 if there are any.
+    Diff { old: PathBuf, new: PathBuf },
+    /// Save a stickfigure for another Stick Nodes version and build.
+    Migrate {
+        input: PathBuf,
+        output: PathBuf,
+        /// Target version, e.g. 403 for 4.0.3.
+        #[arg(long)]
+        to_version: i32,
+        /// Target build. Defaults to the latest supported build for the latest version, or the stickfigure's current build otherwise.
+        #[arg(long)]
+        to_build: Option<i32>,
+    },
+    /// Print the JSON Schema of the JSON that `convert` reads and writes.
+    Schema,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Nodes,
+    Json,
+    Ron,
+    Text,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("nodes") => Ok(Format::Nodes),
+            Some("json") => Ok(Format::Json),
+            Some("ron") => Ok(Format::Ron),
+            Some("txt") => Ok(Format::Text),
+            _ => Err(format!(
+                "Cannot tell the format of {} from its extension. Use .nodes, .json, .ron or .txt, or pass --from/--to.",
+                path.display()
+            )),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(exit_code) => exit_code,
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode, Box<dyn Error>> {
+    match cli.command {
+        Command::Info { input } => {
+            let stickfigure = read(&input, None)?;
+            println!("version: {}", stickfigure.version);
+            println!("build: {}", stickfigure.build);
+            println!("scale: {}", stickfigure.scale);
+            println!("nodes: {}", stickfigure.nodes.node_count());
+            println!("polyfills: {}", stickfigure.polyfills.len());
+            println!(
+                "connectors: {}",
+                stickfigure.nodes.node_weights().filter(|node| node.connector_data.is_some()).count()
+            );
+        }
+        Command::Validate { input } => {
+            let diagnostics = read(&input, None)?.validate();
+            if diagnostics.is_empty() {
+                println!("No problems found.");
+            }
+            for diagnostic in &diagnostics {
+                println!("{diagnostic}");
+            }
+            if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Convert { input, output, from, to } => {
+            let stickfigure = read(&input, from)?;
+            write(&stickfigure, &output, to)?;
+        }
+        Command::Render {
+            input,
+            output,
+            width,
+            height,
+            background,
+        } => {
+            let stickfigure = read(&input, None)?;
+            let background = background.map(|hex| Color::from_hex(&hex)).transpose()?;
+            match output.extension().and_then(|extension| extension.to_str()) {
+                Some("svg") => {
+                    let options = SvgOptions {
+                        background,
+                        width: Some(width as f32),
+                        ..Default::default()
+                    };
+                    fs::write(&output, stickfigure.to_svg(options))?;
+                }
+                Some("png") => {
+                    let mut options = RasterOptions {
+                        width,
+                        background: background.unwrap_or(Color::from_rgba(0, 0, 0, 0)),
+                        ..Default::default()
+                    };
+                    let size = stickfigure.bounds().map(|bounds| bounds.size() + 2.0 * options.padding);
+                    options.height = height.unwrap_or_else(|| match size {
+                        Some(size) if size.x > 0.0 => ((width as f32 * size.y / size.x).ceil() as u32).max(1),
+                        _ => width,
+                    });
+                    stickfigure.render(&options).write_png(fs::File::create(&output)?)?;
+                }
+                _ => return Err(format!("Cannot render to {}. Use a .svg or .png file.", output.display()).into()),
+            }
+        }
+        Command::Diff { old, new } => {
+            let diff = read(&old, None)?.diff(&read(&new, None)?);
+            print!("{diff}");
+            if !diff.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Migrate {
+            input,
+            output,
+            to_version,
+            to_build,
+        } => {
+            let mut stickfigure = read(&input, None)?;
+            let latest = Stickfigure::new();
+            let to_build = to_build.unwrap_or(if to_version == latest.version { latest.build } else { stickfigure.build });
+            stickfigure.set_version_and_build(to_version, to_build)?;
+            write(&stickfigure, &output, None)?;
+        }
+        Command::Schema => {
+            println!("{}", serde_json::to_string_pretty(&SerializableStickfigure::json_schema())?);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn read(path: &Path, format: Option<Format>) -> Result<Stickfigure, Box<dyn Error>> {
+    let format = match format {
+        Some(format) => format,
+        None => Format::from_path(path)?,
+    };
+    let bytes = fs::read(path).map_err(|err| format!("Cannot read {}: {err}", path.display()))?;
+
+    let stickfigure = match format {
+        Format::Nodes => Stickfigure::from_bytes(bytes)?,
+        Format::Json => Stickfigure::from_serializable(&serde_json::from_slice::<SerializableStickfigure>(&bytes)?)?,
+        Format::Ron => Stickfigure::from_serializable(&ron::de::from_bytes::<SerializableStickfigure>(&bytes)?)?,
+        Format::Text => Stickfigure::from_text(&String::from_utf8(bytes)?)?,
+    };
+    Ok(stickfigure)
+}
+
+fn write(stickfigure: &Stickfigure, path: &Path, format: Option<Format>) -> Result<(), Box<dyn Error>> {
+    let format = match format {
+        Some(format) => format,
+        None => Format::from_path(path)?,
+    };
+
+    let bytes = match format {
+        Format::Nodes => stickfigure.to_bytes()?,
+        Format::Json => serde_json::to_vec_pretty(&stickfigure.to_serializable())?,
+        Format::Ron => ron::ser::to_string_pretty(&stickfigure.to_serializable(), ron::ser::PrettyConfig::default())?.into_bytes(),
+        Format::Text => stickfigure.to_text().into_bytes(),
+    };
+    fs::write(path, bytes).map_err(|err| format!("Cannot write {}: {err}", path.display()))?;
+    Ok(())
+}
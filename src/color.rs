@@ -7,7 +7,8 @@ use crate::ColorError;
 
 use libm::{self, Libm};
 
-#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Color {
     pub alpha: u8,
     pub blue: u8,
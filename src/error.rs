@@ -51,6 +51,21 @@ pub enum StickfigureError {
 
     #[error("Draw order index {0} is already occupied. {1}")]
     OccupiedDrawIndex(i32, String),
+
+    #[error("The root node must stay at draw order index 0. {0}")]
+    RootNodeDrawOrder(String),
+
+    #[error("{0} is not supported by Stick Nodes version {1} build {2}.")]
+    UnsupportedFeature(String, i32, i32),
+
+    #[error("Invalid connector: {0}")]
+    InvalidConnector(String),
+
+    #[error("Invalid keyframe: {0}")]
+    InvalidKeyframe(String),
+
+    #[error("Invalid stickfigure text on line {0}: {1}")]
+    InvalidText(usize, String),
 }
 
 #[derive(Error, Debug)]
@@ -5,8 +5,13 @@ extern crate std;
 
 mod color;
 mod error;
+mod render;
 mod serialization;
 mod structs;
+#[cfg(test)]
+mod test_util;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 pub use color::Color;
 pub use error::*;
@@ -18,10 +23,55 @@ pub use structs::node::GradientMode;
 pub use structs::node::AngleLockMode;
 pub use structs::node::TriangleType;
 pub use structs::polyfill::Polyfill;
+pub use structs::polyfill::PolyfillMesh;
 pub use structs::polyfill::PolyfillOptions;
+pub use structs::kinematics::IkOptions;
+pub use structs::kinematics::IkSolver;
+pub use structs::pose::NodeTransform;
+pub use structs::pose::Pose;
+pub use structs::diff::ConnectorChange;
+pub use structs::diff::NodeChange;
+pub use structs::diff::NodeMove;
+pub use structs::diff::PolyfillChange;
+pub use structs::diff::PropertyChange;
+pub use structs::diff::StickfigureDiff;
+pub use structs::merge::merge3;
+pub use structs::merge::Conflict;
+pub use structs::merge::ConflictSubject;
+pub use structs::merge::Conflicts;
+pub use structs::matching::match_nodes;
+pub use structs::matching::Mapping;
+pub use structs::matching::NodeMatch;
+pub use structs::matching::MIN_MATCH_CONFIDENCE;
+pub use structs::pose_snapshot::NodeState;
+pub use structs::pose_snapshot::PoseSnapshot;
+pub use structs::animation::Animation;
+pub use structs::animation::AngleInterpolation;
+pub use structs::animation::Easing;
+pub use structs::animation::Keyframe;
+pub use structs::shape::FillRule;
+pub use structs::shape::NodeShape;
+pub use structs::shape::CIRCLE_SEGMENTS;
 pub use structs::stickfigure::DrawOrderIndex;
 pub use structs::stickfigure::IWillNotAbuseUnlimitedNodes;
 pub use structs::stickfigure::Stickfigure;
+pub use structs::stickfigure::SerializableStickfigure;
+pub use structs::node::SerializableNode;
 pub use structs::connector::ConnectorData;
 pub use structs::connector::ConnectorMethod;
+pub use structs::bounds::Bounds;
+pub use structs::capabilities::Capabilities;
+pub use structs::capabilities::NODE_LIMIT;
+pub use structs::diagnostic::Diagnostic;
+pub use structs::diagnostic::DiagnosticFix;
+pub use structs::diagnostic::Severity;
 pub use structs::stickfigure::SUPPORTED_APP_VERSION;
+pub use structs::stickfigure::MIN_WRITABLE_VERSION;
+#[cfg(feature = "svg")]
+pub use render::svg::SvgOptions;
+#[cfg(feature = "raster")]
+pub use render::raster::RasterOptions;
+#[cfg(feature = "raster")]
+pub use render::raster::RgbaImage;
+#[cfg(feature = "wasm")]
+pub use wasm::WasmStickfigure;
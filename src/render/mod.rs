@@ -0,0 +1,5 @@
+#[cfg(feature = "raster")]
+pub(crate) mod raster;
+pub(crate) mod scene;
+#[cfg(feature = "svg")]
+pub(crate) mod svg;
@@ -0,0 +1,375 @@
+use glam::Vec2;
+extern crate alloc;
+use alloc::{vec, vec::Vec};
+
+use super::scene::{scene_bounds, Paint};
+use crate::structs::bounds::triangle_contains;
+use crate::{Color, FillRule, Pose, Stickfigure};
+
+/// Settings for `Stickfigure::render`.
+#[derive(Debug, Clone)]
+pub struct RasterOptions {
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Height of the image in pixels.
+    pub height: u32,
+    /// Pixels per figure unit. If `None`, the figure is scaled to fit the image, leaving `padding` pixels around it.
+    pub zoom: Option<f32>,
+    /// Point of the figure at the center of the image. If `None`, the center of the figure's bounds.
+    pub center: Option<Vec2>,
+    /// Space left around the figure when `zoom` is `None`, in pixels.
+    pub padding: f32,
+    /// Color filling the image behind the figure.
+    pub background: Color,
+    /// How parts of polyfill outlines that cross over other parts are filled.
+    pub polyfill_fill_rule: FillRule,
+    /// Antialiasing quality: each pixel is sampled on a grid of this many samples per side, from 1 (no antialiasing) to 4.
+    pub samples_per_axis: u8,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        Self {
+            width: 256,
+            height: 256,
+            zoom: None,
+            center: None,
+            padding: 8.0,
+            background: Color::from_rgba(0, 0, 0, 0),
+            polyfill_fill_rule: FillRule::default(),
+            samples_per_axis: 4,
+        }
+    }
+}
+
+/// An image with 8-bit red, green, blue and alpha channels, not premultiplied, in rows from top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    /// `width * height * 4` bytes.
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Gets the color of the pixel at `x`, `y`, if it is inside the image.
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = ((y * self.width + x) * 4) as usize;
+        Some(Color::from_rgba(
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ))
+    }
+
+    /// Encodes the image as a PNG file.
+    #[cfg(feature = "std")]
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut scanlines = Vec::with_capacity(self.pixels.len() + self.height as usize);
+        for row in self.pixels.chunks_exact((self.width as usize * 4).max(1)).take(self.height as usize) {
+            scanlines.push(0); // No filter.
+            scanlines.extend_from_slice(row);
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlace.
+
+        let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+        write_png_chunk(&mut png, b"IHDR", &header);
+        write_png_chunk(&mut png, b"IDAT", &miniz_oxide::deflate::compress_to_vec_zlib(&scanlines, 6));
+        write_png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// Encodes the image as a PNG file into `writer`.
+    #[cfg(feature = "std")]
+    pub fn write_png<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        writer.write_all(&self.to_png())
+    }
+}
+
+impl Stickfigure {
+    /// Renders the stickfigure, as currently posed, to an image. See `render_pose`.
+    pub fn render(&self, options: &RasterOptions) -> RgbaImage {
+        self.render_pose(&self.compute_pose(), options)
+    }
+
+    /// Renders the stickfigure in `pose` to an image, without a GPU.
+    ///
+    /// Draws the same shapes as `to_svg`, back to front in draw order, blending each over what is beneath it with its alpha.
+    /// Edges are antialiased by sampling each pixel `options.samples_per_axis` squared times.
+    ///
+    /// # Parameters
+    ///
+    /// * `pose` - Positions of the nodes, from `compute_pose`.
+    /// * `options` - Size of the image, what part of the figure it shows and how.
+    ///
+    /// # Returns
+    ///
+    /// The rendered `RgbaImage`.
+    pub fn render_pose(&self, pose: &Pose, options: &RasterOptions) -> RgbaImage {
+        let (width, height) = (options.width as usize, options.height as usize);
+        let items = self.scene(pose, options.polyfill_fill_rule);
+
+        let (min, max) = scene_bounds(&items).unwrap_or((Vec2::ZERO, Vec2::ZERO));
+        let center = options.center.unwrap_or((min + max) / 2.0);
+        let zoom = options.zoom.unwrap_or_else(|| {
+            let size = (max - min).max(Vec2::splat(f32::EPSILON));
+            let available = Vec2::new(width as f32, height as f32) - Vec2::splat(options.padding * 2.0);
+            (available / size).min_element().max(f32::EPSILON)
+        });
+        let to_pixels = |point: Vec2| (point - center) * zoom + Vec2::new(width as f32, height as f32) / 2.0;
+
+        let background = premultiply(options.background);
+        let mut canvas: Vec<[f32; 4]> = vec![background; width * height];
+
+        let samples_per_axis = options.samples_per_axis.clamp(1, 4) as usize;
+        let sample_count = (samples_per_axis * samples_per_axis) as f32;
+        let sample_offsets: Vec<Vec2> = (0..samples_per_axis * samples_per_axis)
+            .map(|i| {
+                Vec2::new(
+                    ((i % samples_per_axis) as f32 + 0.5) / samples_per_axis as f32,
+                    ((i / samples_per_axis) as f32 + 0.5) / samples_per_axis as f32,
+                )
+            })
+            .collect();
+
+        for item in &items {
+            let triangles: Vec<[Vec2; 3]> = item
+                .shape
+                .triangles
+                .iter()
+                .map(|triangle| triangle.map(to_pixels))
+                .collect();
+            let Some((item_min, item_max)) = pixel_bounds(&triangles, width, height) else {
+                continue;
+            };
+
+            // Which samples of each pixel the item covers. Triangles of one item never overlap, but marking samples rather
+            // than adding up coverage keeps shared edges from being counted twice.
+            let mask_width = item_max.0 - item_min.0;
+            let mut mask = vec![0u16; mask_width * (item_max.1 - item_min.1)];
+
+            for triangle in &triangles {
+                let Some((triangle_min, triangle_max)) = pixel_bounds(core::slice::from_ref(triangle), width, height) else {
+                    continue;
+                };
+                for y in triangle_min.1..triangle_max.1 {
+                    for x in triangle_min.0..triangle_max.0 {
+                        let pixel = Vec2::new(x as f32, y as f32);
+                        let covered = sample_offsets
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, offset)| triangle_contains(triangle, pixel + **offset))
+                            .fold(0u16, |bits, (i, _)| bits | (1 << i));
+                        mask[(y - item_min.1) * mask_width + (x - item_min.0)] |= covered;
+                    }
+                }
+            }
+
+            for y in item_min.1..item_max.1 {
+                for x in item_min.0..item_max.0 {
+                    let covered = mask[(y - item_min.1) * mask_width + (x - item_min.0)];
+                    if covered == 0 {
+                        continue;
+                    }
+
+                    let coverage = covered.count_ones() as f32 / sample_count;
+                    let point = (Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - Vec2::new(width as f32, height as f32) / 2.0) / zoom + center;
+                    let source = premultiply(paint_at(&item.paint, point));
+                    let destination = &mut canvas[y * width + x];
+                    for channel in 0..4 {
+                        destination[channel] = source[channel] * coverage + destination[channel] * (1.0 - source[3] * coverage);
+                    }
+                }
+            }
+        }
+
+        let pixels = canvas
+            .iter()
+            .flat_map(|pixel| {
+                let alpha = pixel[3];
+                let unpremultiply = |channel: f32| if alpha > 0.0 { channel / alpha } else { 0.0 };
+                [
+                    to_byte(unpremultiply(pixel[0])),
+                    to_byte(unpremultiply(pixel[1])),
+                    to_byte(unpremultiply(pixel[2])),
+                    to_byte(alpha),
+                ]
+            })
+            .collect();
+
+        RgbaImage {
+            width: options.width,
+            height: options.height,
+            pixels,
+        }
+    }
+}
+
+/// Range of pixels, as `(x, y)` from inclusive to exclusive, that `triangles` may touch within the image.
+fn pixel_bounds(triangles: &[[Vec2; 3]], width: usize, height: usize) -> Option<((usize, usize), (usize, usize))> {
+    let (min, max) = triangles.iter().flatten().fold(
+        (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+        |(min, max), point| (min.min(*point), max.max(*point)),
+    );
+
+    let from = (libm::floorf(min.x).max(0.0) as usize, libm::floorf(min.y).max(0.0) as usize);
+    let to = (
+        (libm::ceilf(max.x).max(0.0) as usize).min(width),
+        (libm::ceilf(max.y).max(0.0) as usize).min(height),
+    );
+
+    if !min.is_finite() || !max.is_finite() || from.0 >= to.0 || from.1 >= to.1 {
+        return None;
+    }
+    Some((from, to))
+}
+
+fn paint_at(paint: &Paint, point: Vec2) -> Color {
+    match *paint {
+        Paint::Solid(color) => color,
+        Paint::LinearGradient {
+            from,
+            to,
+            from_color,
+            to_color,
+        } => {
+            let span = to - from;
+            let t = if span.length_squared() > 0.0 {
+                ((point - from).dot(span) / span.length_squared()).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let mix = |a: u8, b: u8| to_byte((a as f32 + (b as f32 - a as f32) * t) / 255.0);
+            Color::from_rgba(
+                mix(from_color.red, to_color.red),
+                mix(from_color.green, to_color.green),
+                mix(from_color.blue, to_color.blue),
+                mix(from_color.alpha, to_color.alpha),
+            )
+        }
+    }
+}
+
+fn premultiply(color: Color) -> [f32; 4] {
+    let alpha = color.alpha as f32 / 255.0;
+    [
+        color.red as f32 / 255.0 * alpha,
+        color.green as f32 / 255.0 * alpha,
+        color.blue as f32 / 255.0 * alpha,
+        alpha,
+    ]
+}
+
+fn to_byte(value: f32) -> u8 {
+    libm::roundf(value.clamp(0.0, 1.0) * 255.0) as u8
+}
+
+#[cfg(feature = "std")]
+fn write_png_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+    png.extend_from_slice(&crc32(chunk_type.iter().chain(data)).to_be_bytes());
+}
+
+/// CRC-32 as used by PNG chunks.
+#[cfg(feature = "std")]
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let crc = bytes.fold(0xFFFF_FFFFu32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| {
+            if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 }
+        })
+    });
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+    use crate::DrawOrderIndex;
+
+    const RED: Color = Color {
+        red: 200,
+        green: 30,
+        blue: 30,
+        alpha: 255,
+    };
+    const WHITE: Color = Color {
+        red: 255,
+        green: 255,
+        blue: 255,
+        alpha: 255,
+    };
+
+    /// A red stickfigure with one node of length 100 and thickness 32, rendered to 64 by 48 pixels on white.
+    fn rendered() -> RgbaImage {
+        let mut stickfigure = Stickfigure::new();
+        stickfigure.color = RED;
+        stickfigure.add_node(segment(100.0, 0.0), DrawOrderIndex(0)).unwrap();
+
+        stickfigure.render(&RasterOptions {
+            width: 64,
+            height: 48,
+            background: WHITE,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn fits_the_stickfigure_in_the_image() {
+        let image = rendered();
+
+        assert_eq!((image.width, image.height), (64, 48));
+        assert_eq!(image.pixels.len(), 64 * 48 * 4);
+        assert_eq!(image.get_pixel(32, 24), Some(RED));
+        assert_eq!(image.get_pixel(0, 0), Some(WHITE));
+        assert_eq!(image.get_pixel(64, 0), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn writes_a_png_that_decodes_to_the_image() {
+        let image = rendered();
+        let mut png = Vec::new();
+        image.write_png(&mut png).unwrap();
+
+        assert_eq!(png[..8], [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n']);
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (chunk_type, data) = (&rest[4..8], &rest[8..8 + length]);
+            let crc = u32::from_be_bytes(rest[8 + length..12 + length].try_into().unwrap());
+            assert_eq!(crc, crc32(chunk_type.iter().chain(data)));
+            chunks.push((chunk_type, data));
+            rest = &rest[12 + length..];
+        }
+        // The CRC every PNG ends with, which doesn't depend on the image.
+        assert_eq!(png[png.len() - 4..], [0xAE, 0x42, 0x60, 0x82]);
+
+        let chunk_types: Vec<&[u8]> = chunks.iter().map(|(chunk_type, _)| *chunk_type).collect();
+        assert_eq!(chunk_types, [b"IHDR", b"IDAT", b"IEND"]);
+        let header = chunks[0].1;
+        assert_eq!(u32::from_be_bytes(header[..4].try_into().unwrap()), 64);
+        assert_eq!(u32::from_be_bytes(header[4..8].try_into().unwrap()), 48);
+
+        let scanlines = miniz_oxide::inflate::decompress_to_vec_zlib(chunks[1].1).unwrap();
+        let stride = 1 + 64 * 4;
+        assert_eq!(scanlines.len(), 48 * stride);
+        let pixel = |x: usize, y: usize| {
+            let i = y * stride + 1 + x * 4;
+            Color::from_rgba(scanlines[i], scanlines[i + 1], scanlines[i + 2], scanlines[i + 3])
+        };
+        assert_eq!(pixel(32, 24), RED);
+        assert_eq!(pixel(0, 0), WHITE);
+    }
+}
@@ -0,0 +1,145 @@
+use glam::Vec2;
+extern crate alloc;
+use alloc::{vec, vec::Vec};
+
+use crate::structs::shape::triangulate;
+use crate::{Color, DrawOrderIndex, FillRule, GradientMode, Node, NodeShape, NodeType, Pose, Stickfigure};
+
+/// What part of the stickfigure a `SceneItem` draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SceneItemKind {
+    Node,
+    CircleOutline,
+    Polyfill,
+}
+
+/// How a `SceneItem` is filled.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(any(feature = "svg", feature = "raster")), allow(dead_code))]
+pub(crate) enum Paint {
+    Solid(Color),
+    /// Blends from `from_color` at `from` to `to_color` at `to`, constant along lines perpendicular to that span.
+    LinearGradient {
+        from: Vec2,
+        to: Vec2,
+        from_color: Color,
+        to_color: Color,
+    },
+}
+
+/// A filled shape to draw, in global coordinates.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "svg"), allow(dead_code))]
+pub(crate) struct SceneItem {
+    /// The node drawn, or the anchor node of the polyfill drawn.
+    pub draw_index: DrawOrderIndex,
+    pub kind: SceneItemKind,
+    pub shape: NodeShape,
+    pub paint: Paint,
+    pub fill_rule: FillRule,
+}
+
+impl Stickfigure {
+    /// Lists everything to draw for the stickfigure in `pose`, back to front.
+    ///
+    /// Nodes are drawn in draw order. Each polyfill is drawn just beneath its anchor node, and each circle outline just
+    /// above its node.
+    pub(crate) fn scene(&self, pose: &Pose, polyfill_fill_rule: FillRule) -> Vec<SceneItem> {
+        let mut draw_indices = self.get_all_node_indices();
+        draw_indices.sort();
+
+        let mut items = Vec::new();
+        for draw_index in draw_indices {
+            let Some(node) = self.get_node(draw_index) else {
+                continue;
+            };
+
+            for polyfill in self.polyfills.iter().filter(|polyfill| polyfill.anchor_node_draw_index == draw_index) {
+                let outline = polyfill.get_global_outline(pose, self);
+                items.push(SceneItem {
+                    draw_index,
+                    kind: SceneItemKind::Polyfill,
+                    shape: NodeShape {
+                        triangles: triangulate(core::slice::from_ref(&outline), polyfill_fill_rule),
+                        contours: vec![outline],
+                    },
+                    paint: Paint::Solid(polyfill.get_display_color(self)),
+                    fill_rule: polyfill_fill_rule,
+                });
+            }
+
+            if node.node_type == NodeType::RootNode {
+                continue;
+            }
+
+            items.push(SceneItem {
+                draw_index,
+                kind: SceneItemKind::Node,
+                shape: node.shape(pose),
+                paint: node_paint(node, pose, self),
+                fill_rule: FillRule::NonZero,
+            });
+
+            if let Some(outline) = node.circle_outline_shape(pose) {
+                items.push(SceneItem {
+                    draw_index,
+                    kind: SceneItemKind::CircleOutline,
+                    shape: outline,
+                    paint: Paint::Solid(node.circle_outline_color),
+                    fill_rule: FillRule::NonZero,
+                });
+            }
+        }
+
+        items
+    }
+}
+
+/// Smallest and largest corners of the box around every item, or `None` if there is nothing to draw.
+pub(crate) fn scene_bounds(items: &[SceneItem]) -> Option<(Vec2, Vec2)> {
+    items
+        .iter()
+        .flat_map(|item| item.shape.contours.iter().flatten())
+        .fold(None, |bounds, point| match bounds {
+            None => Some((*point, *point)),
+            Some((min, max)) => Some((min.min(*point), max.max(*point))),
+        })
+}
+
+/// Resolves a node's paint.
+///
+/// A `GradientMode::Normal` gradient runs along the node from its display color at the start to `gradient_color` at the end.
+/// A `GradientMode::Sideways` gradient runs across the node, from its right edge to its left edge. `reverse_gradient` swaps the two colors.
+fn node_paint(node: &Node, pose: &Pose, stickfigure: &Stickfigure) -> Paint {
+    let color = node.get_display_color(stickfigure);
+    let Some(transform) = pose.get(node.get_draw_order_index()).filter(|_| node.use_gradient) else {
+        return Paint::Solid(color);
+    };
+
+    let (from_color, to_color) = if node.reverse_gradient {
+        (node.gradient_color, color)
+    } else {
+        (color, node.gradient_color)
+    };
+
+    let (from, to) = match node.gradient_mode {
+        GradientMode::Normal => (transform.start, transform.end),
+        GradientMode::Sideways => {
+            let center = (transform.start + transform.end) / 2.0;
+            let half_width = match node.node_type {
+                NodeType::Circle | NodeType::FilledCircle | NodeType::Polygon => transform.start.distance(transform.end) / 2.0,
+                _ => transform.thickness / 2.0,
+            };
+            let angle = transform.angle.to_radians();
+            let across = Vec2::new(-libm::sinf(angle), libm::cosf(angle)) * half_width;
+            (center - across, center + across)
+        }
+    };
+
+    Paint::LinearGradient {
+        from,
+        to,
+        from_color,
+        to_color,
+    }
+}
@@ -0,0 +1,180 @@
+use core::fmt::Write;
+
+use glam::Vec2;
+extern crate alloc;
+use alloc::{format, string::String};
+
+use super::scene::{scene_bounds, Paint, SceneItemKind};
+use crate::{Color, FillRule, Stickfigure};
+
+/// Settings for `Stickfigure::to_svg`.
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    /// Space left around the figure, in the figure's own units.
+    pub padding: f32,
+    /// Color filling the whole image behind the figure. Transparent if `None`.
+    pub background: Option<Color>,
+    /// Width of the image in pixels. The height follows from the figure's proportions. If `None`, one figure unit is one pixel.
+    pub width: Option<f32>,
+    /// How parts of polyfill outlines that cross over other parts are filled.
+    pub polyfill_fill_rule: FillRule,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            padding: 8.0,
+            background: None,
+            width: None,
+            polyfill_fill_rule: FillRule::default(),
+        }
+    }
+}
+
+impl Stickfigure {
+    /// Draws the stickfigure, as currently posed, as an SVG document.
+    ///
+    /// Every node shape (see `Node::shape`), circle outline and polyfill is drawn as a path, back to front in draw order,
+    /// with each polyfill just beneath its anchor node. Paths carry a `data-draw-index` attribute with the draw order
+    /// index of their node, or of the anchor node for polyfills. The viewBox fits the figure's bounds plus `options.padding`.
+    pub fn to_svg(&self, options: SvgOptions) -> String {
+        let pose = self.compute_pose();
+        let items = self.scene(&pose, options.polyfill_fill_rule);
+
+        let (min, max) = scene_bounds(&items).unwrap_or((Vec2::ZERO, Vec2::ZERO));
+        let min = min - Vec2::splat(options.padding);
+        let size = (max - min + Vec2::splat(options.padding)).max(Vec2::splat(f32::EPSILON));
+        let (width, height) = match options.width {
+            Some(width) => (width, width * size.y / size.x),
+            None => (size.x, size.y),
+        };
+
+        let mut svg = String::new();
+        let _ = write!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+            number(min.x),
+            number(min.y),
+            number(size.x),
+            number(size.y),
+            number(width),
+            number(height)
+        );
+
+        if let Some(background) = options.background {
+            let _ = write!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}"{}/>"#,
+                number(min.x),
+                number(min.y),
+                number(size.x),
+                number(size.y),
+                fill(&background, "fill")
+            );
+        }
+
+        for (i, item) in items.iter().enumerate() {
+            let mut path = String::new();
+            for contour in item.shape.contours.iter().filter(|contour| contour.len() >= 3) {
+                for (j, point) in contour.iter().enumerate() {
+                    let command = if j == 0 { 'M' } else { 'L' };
+                    let _ = write!(path, "{}{} {}", command, number(point.x), number(point.y));
+                }
+                path.push('Z');
+            }
+            if path.is_empty() {
+                continue;
+            }
+
+            let paint = match item.paint {
+                Paint::Solid(color) => fill(&color, "fill"),
+                Paint::LinearGradient {
+                    from,
+                    to,
+                    from_color,
+                    to_color,
+                } => {
+                    let _ = write!(
+                        svg,
+                        r#"<defs><linearGradient id="gradient-{}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}"><stop offset="0"{}/><stop offset="1"{}/></linearGradient></defs>"#,
+                        i,
+                        number(from.x),
+                        number(from.y),
+                        number(to.x),
+                        number(to.y),
+                        fill(&from_color, "stop-color"),
+                        fill(&to_color, "stop-color")
+                    );
+                    format!(r#" fill="url(#gradient-{})""#, i)
+                }
+            };
+
+            let fill_rule = match item.fill_rule {
+                FillRule::EvenOdd => "evenodd",
+                FillRule::NonZero => "nonzero",
+            };
+            let class = match item.kind {
+                SceneItemKind::Node => "node",
+                SceneItemKind::CircleOutline => "circle-outline",
+                SceneItemKind::Polyfill => "polyfill",
+            };
+
+            let _ = write!(
+                svg,
+                r#"<path class="{}" data-draw-index="{}" d="{}"{} fill-rule="{}"/>"#,
+                class, item.draw_index.0, path, paint, fill_rule
+            );
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+/// Formats a coordinate with at most 3 decimals and no trailing zeros.
+fn number(value: f32) -> String {
+    let rounded = libm::roundf(value * 1000.0) / 1000.0;
+    format!("{}", rounded + 0.0)
+}
+
+/// Attributes painting with `color`, where `attribute` is e.g. `fill` or `stop-color`. Opacity is left out when the color is opaque.
+fn fill(color: &Color, attribute: &str) -> String {
+    let opacity_attribute = if attribute == "fill" { "fill-opacity" } else { "stop-opacity" };
+    let mut attributes = format!(
+        r#" {}="rgb({},{},{})""#,
+        attribute, color.red, color.green, color.blue
+    );
+    if color.alpha != 255 {
+        let _ = write!(attributes, r#" {}="{}""#, opacity_attribute, number(color.alpha as f32 / 255.0));
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+    use crate::{DrawOrderIndex, NodeType};
+
+    #[test]
+    fn fits_the_view_box_to_the_figure_and_draws_one_path_per_node() {
+        let mut stickfigure = Stickfigure::new();
+        let arm = stickfigure.add_node(segment(100.0, 0.0), DrawOrderIndex(0)).unwrap();
+        let hand = stickfigure.add_node(segment(50.0, 90.0), arm).unwrap();
+        for draw_index in [arm, hand] {
+            stickfigure
+                .update_node(draw_index, |node| node.node_type = NodeType::Segment)
+                .unwrap();
+        }
+
+        let svg = stickfigure.to_svg(SvgOptions::default());
+
+        // Both nodes are 32 thick: x from 0 to 116 and y from -16 to 50, plus 8 of padding.
+        let header = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-8 -24 132 82" width="132" height="82">"#;
+        assert!(svg.starts_with(header), "{svg}");
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<path ").count(), 2);
+        assert_eq!(svg.matches(r#"<path class="node" data-draw-index="1""#).count(), 1);
+        assert_eq!(svg.matches(r#"<path class="node" data-draw-index="2""#).count(), 1);
+    }
+}
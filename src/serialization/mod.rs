@@ -1,2 +1,3 @@
 pub(crate) mod read;
 pub(crate) mod write;
+pub(crate) mod text;
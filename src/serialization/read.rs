@@ -1,11 +1,9 @@
 use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt};
 use core2::io::{self, Read, Seek, SeekFrom};
 extern crate alloc;
-use alloc::{rc::Rc, vec::Vec, vec, format};
+use alloc::{string::String, vec::Vec, vec, format};
 use miniz_oxide::inflate::decompress_to_vec_zlib;
 
-use core::{cell::RefCell};
-
 use crate::{
     error::*,
     structs::{node::*, polyfill::*, stickfigure::*},
@@ -117,12 +115,12 @@ pub fn read_stickfigure<R: Read + Seek>(reader: &mut R) -> Result<Stickfigure, L
                     .read_i32::<BigEndian>()
                     .or_else(|err| return Err(StickfigureError::Io(err)))?;
                 
-                let connector_node = stickfigure.get_node(DrawOrderIndex(my_draw_index)).ok_or_else(|| StickfigureError::InvalidDrawIndex(my_draw_index, format!("Attempted to get connector node defined in .nodes file that does not exist(?)")))?;
                 if !stickfigure.draw_index_exists(DrawOrderIndex(end_draw_index)) {
                     return Err(StickfigureError::InvalidDrawIndex(end_draw_index, format!("Attempted to get end connector node defined in .nodes file that does not exist(?)")))?;
                 }
 
-                let connector_data= &mut connector_node.borrow_mut().connector_data;
+                let connector_node = stickfigure.get_node_mut(DrawOrderIndex(my_draw_index)).ok_or_else(|| StickfigureError::InvalidDrawIndex(my_draw_index, String::from("Attempted to get connector node defined in .nodes file that does not exist(?)")))?;
+                let connector_data = &mut connector_node.connector_data;
 
                 match connector_data {
                     Some(data) => {
@@ -157,6 +155,14 @@ fn read_child_nodes<E: ByteOrder>(
 
         if parent_draw_index.0 == -1 {
             stickfigure.add_root_node();
+            // Keep what the file stores for the root node, as `from_serializable` and `from_text` do.
+            if let Some(root_node) = stickfigure.get_node_mut(DrawOrderIndex(0)) {
+                *root_node = Node {
+                    node_type: NodeType::RootNode,
+                    draw_order_index: DrawOrderIndex(0),
+                    ..node
+                };
+            }
         } else {
             stickfigure.add_node_at_unique_index(node, parent_draw_index, draw_index)?;
         }
@@ -370,6 +376,10 @@ fn read_node<E: ByteOrder>(
                 .read_f32::<E>()
                 .or_else(|err| return Err(StickfigureError::Io(err)))?;
         }
+    } else {
+        // Before build 36 trapezoids only taper by `trapezoid_top_thickness_ratio`.
+        node.use_trapezoid_thickness_start = false;
+        node.use_trapezoid_thickness_end = false;
     }
     if version >= 403 && build == 36 {
         reader.read_i32::<E>().or_else(|err| return Err(StickfigureError::Io(err)))?;
@@ -597,14 +607,14 @@ fn read_node<E: ByteOrder>(
 
 fn read_polyfill_header<E: ByteOrder>(
     reader: &mut impl Read,
-) -> Result<Vec<Rc<RefCell<Polyfill>>>, StickfigureError> {
+) -> Result<Vec<Polyfill>, StickfigureError> {
     let number_of_polyfills = reader
         .read_i32::<E>()
         .or_else(|err| return Err(StickfigureError::Io(err)))?;
 
     let mut polyfills = Vec::with_capacity(number_of_polyfills as usize);
     for _ in 0..number_of_polyfills {
-        let polyfill = Rc::new(RefCell::new(read_polyfill::<E>(reader)?));
+        let polyfill = read_polyfill::<E>(reader)?;
         polyfills.push(polyfill);
     }
 
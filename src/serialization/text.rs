@@ -0,0 +1,778 @@
+extern crate alloc;
+use alloc::{format, string::String, string::ToString, vec::Vec};
+use core::fmt::Write;
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+    error::*,
+    structs::{diff::with_node_fields, node::*, polyfill::*, stickfigure::*},
+    AngleLockMode, Color, ConnectorData, ConnectorMethod,
+};
+
+/// A value that can be written as, and read back from, a single word of stickfigure text.
+trait TextValue: Sized {
+    fn to_word(&self) -> String;
+    fn from_word(word: &str) -> Option<Self>;
+}
+
+impl TextValue for bool {
+    fn to_word(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_word(word: &str) -> Option<Self> {
+        word.parse().ok()
+    }
+}
+
+macro_rules! impl_text_value_for_numbers {
+    ($($number:ty),+) => {
+        $(
+            impl TextValue for $number {
+                fn to_word(&self) -> String {
+                    self.to_string()
+                }
+
+                fn from_word(word: &str) -> Option<Self> {
+                    word.parse().ok()
+                }
+            }
+        )+
+    };
+}
+
+impl_text_value_for_numbers!(i8, i16, i32, f32);
+
+/// Enums are written as the name of their variant.
+macro_rules! impl_text_value_for_enums {
+    ($($enum:ty),+) => {
+        $(
+            impl TextValue for $enum {
+                fn to_word(&self) -> String {
+                    format!("{:?}", self)
+                }
+
+                fn from_word(word: &str) -> Option<Self> {
+                    (i8::MIN..=i8::MAX)
+                        .filter_map(<$enum>::from_integer)
+                        .find(|variant| format!("{:?}", variant) == word)
+                }
+            }
+        )+
+    };
+}
+
+impl_text_value_for_enums!(NodeType, GradientMode, AngleLockMode, TriangleType, ConnectorMethod);
+
+impl TextValue for Color {
+    fn to_word(&self) -> String {
+        self.to_hex()
+    }
+
+    fn from_word(word: &str) -> Option<Self> {
+        word.starts_with('#').then(|| Color::from_hex(word).ok()).flatten()
+    }
+}
+
+/// Writes ` field=value` for every listed field whose value differs from `$default`.
+macro_rules! write_changed_fields {
+    ($out:expr, $value:expr, $default:expr, $($field:ident),+ $(,)?) => {
+        $(
+            if $value.$field != $default.$field {
+                write_attribute($out, stringify!($field), &$value.$field);
+            }
+        )+
+    };
+}
+
+/// Sets the field named by `$attribute` if it is one of the listed fields, evaluating to whether it was.
+macro_rules! set_field {
+    ($attribute:expr, $value:expr, $($field:ident),+ $(,)?) => {
+        match $attribute.key {
+            $(
+                stringify!($field) => {
+                    $value.$field = parse_value($attribute)?;
+                    true
+                }
+            )+
+            _ => false,
+        }
+    };
+}
+
+macro_rules! with_connector_fields {
+    ($macro:ident!($($args:tt)*)) => {
+        $macro!(
+            $($args)*,
+            local_x,
+            local_y,
+            percent,
+            percent_default,
+            value,
+            method,
+            reversed,
+            smart_stretch_ancestral_value
+        )
+    };
+}
+
+const INDENT: &str = "    ";
+
+pub(crate) fn write_text(stickfigure: &Stickfigure) -> String {
+    let mut out = String::from("stickfigure");
+    write_attribute(&mut out, "version", &stickfigure.version);
+    write_attribute(&mut out, "build", &stickfigure.build);
+    let default = Stickfigure::default();
+    write_changed_fields!(&mut out, stickfigure, default, scale, color);
+    out.push_str("\n\n");
+
+    let mut referenced: HashSet<DrawOrderIndex> = HashSet::new();
+    for polyfill in &stickfigure.polyfills {
+        referenced.insert(polyfill.anchor_node_draw_index);
+        referenced.extend(polyfill.attached_node_draw_indices.iter().copied());
+    }
+    for node in stickfigure.nodes.node_weights() {
+        if let Some(connector_data) = &node.connector_data {
+            referenced.insert(connector_data.end_node_draw_index);
+        }
+    }
+
+    let preorder = preorder(stickfigure);
+    let draw_order_is_preorder = preorder.iter().enumerate().all(|(position, draw_index)| draw_index.0 == position as i32);
+
+    let writer = TextWriter {
+        stickfigure,
+        referenced: &referenced,
+        write_draw_indices: !draw_order_is_preorder,
+    };
+    writer.write_node(&mut out, DrawOrderIndex(0), 0);
+
+    if !stickfigure.polyfills.is_empty() {
+        out.push('\n');
+    }
+    let default = Polyfill::default();
+    for polyfill in &stickfigure.polyfills {
+        out.push_str("polyfill anchor=");
+        out.push_str(&name(polyfill.anchor_node_draw_index));
+        out.push_str(" attached=[");
+        let attached: Vec<String> = polyfill.attached_node_draw_indices.iter().map(|draw_index| name(*draw_index)).collect();
+        out.push_str(&attached.join(" "));
+        out.push(']');
+        write_changed_fields!(&mut out, polyfill, default, color, use_polyfill_color);
+        out.push('\n');
+    }
+
+    out
+}
+
+struct TextWriter<'a> {
+    stickfigure: &'a Stickfigure,
+    referenced: &'a HashSet<DrawOrderIndex>,
+    write_draw_indices: bool,
+}
+
+impl TextWriter<'_> {
+    fn write_node(&self, out: &mut String, draw_index: DrawOrderIndex, depth: usize) {
+        let Some(node) = self.stickfigure.get_node(draw_index) else {
+            return;
+        };
+
+        for _ in 0..depth {
+            out.push_str(INDENT);
+        }
+        out.push_str("node");
+        if self.referenced.contains(&draw_index) {
+            out.push(' ');
+            out.push_str(&name(draw_index));
+        }
+        if self.write_draw_indices {
+            write_attribute(out, "draw", &draw_index.0);
+        }
+
+        let default = if draw_index == DrawOrderIndex(0) {
+            Node {
+                node_type: NodeType::RootNode,
+                ..Node::default()
+            }
+        } else {
+            Node::default()
+        };
+        with_node_fields!(write_changed_fields!(out, node, default));
+
+        if let Some(connector_data) = &node.connector_data {
+            out.push_str(" connector=(end=");
+            out.push_str(&name(connector_data.end_node_draw_index));
+            let default = ConnectorData::default();
+            with_connector_fields!(write_changed_fields!(out, connector_data, default));
+            out.push(')');
+        }
+
+        let mut children = self.stickfigure.get_children(draw_index);
+        if children.is_empty() {
+            out.push('\n');
+            return;
+        }
+        children.sort();
+
+        out.push_str(" {\n");
+        for child in children {
+            self.write_node(out, child, depth + 1);
+        }
+        for _ in 0..depth {
+            out.push_str(INDENT);
+        }
+        out.push_str("}\n");
+    }
+}
+
+fn write_attribute<T: TextValue>(out: &mut String, key: &str, value: &T) {
+    let word = value.to_word();
+    if word == "true" {
+        let _ = write!(out, " {}", key);
+    } else {
+        let _ = write!(out, " {}={}", key, word);
+    }
+}
+
+fn name(draw_index: DrawOrderIndex) -> String {
+    format!("@n{}", draw_index.0)
+}
+
+/// Draw order indices of every node, parents before children and siblings in draw order.
+fn preorder(stickfigure: &Stickfigure) -> Vec<DrawOrderIndex> {
+    let mut preorder = Vec::new();
+    let mut stack = Vec::from([DrawOrderIndex(0)]);
+    while let Some(draw_index) = stack.pop() {
+        preorder.push(draw_index);
+        let mut children = stickfigure.get_children(draw_index);
+        children.sort();
+        stack.extend(children.into_iter().rev());
+    }
+    preorder
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Symbol(char),
+}
+
+const SYMBOLS: [char; 7] = ['{', '}', '[', ']', '(', ')', '='];
+
+/// Splits `text` into words and symbols with the line they are on. `//` starts a comment that runs to the end of the line.
+fn tokenize(text: &str) -> Vec<(Token<'_>, usize)> {
+    let mut tokens = Vec::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.split("//").next().unwrap_or_default();
+        let mut rest = line.trim_start();
+        while let Some(first) = rest.chars().next() {
+            if SYMBOLS.contains(&first) {
+                tokens.push((Token::Symbol(first), line_index + 1));
+                rest = &rest[first.len_utf8()..];
+            } else {
+                let end = rest
+                    .find(|character: char| character.is_whitespace() || SYMBOLS.contains(&character))
+                    .unwrap_or(rest.len());
+                tokens.push((Token::Word(&rest[..end]), line_index + 1));
+                rest = &rest[end..];
+            }
+            rest = rest.trim_start();
+        }
+    }
+
+    tokens
+}
+
+enum Value<'a> {
+    /// A key without a value, which means `true`.
+    Flag,
+    Word(&'a str),
+    List(Vec<&'a str>),
+    Group(Vec<Attribute<'a>>),
+}
+
+struct Attribute<'a> {
+    key: &'a str,
+    value: Value<'a>,
+    line: usize,
+}
+
+struct ParsedNode<'a> {
+    name: Option<&'a str>,
+    attributes: Vec<Attribute<'a>>,
+    children: Vec<ParsedNode<'a>>,
+    line: usize,
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token<'a>, usize)>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.position).map(|(token, _)| *token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn error(&self, message: String) -> StickfigureError {
+        StickfigureError::InvalidText(self.line(), message)
+    }
+
+    fn describe_next(&self) -> String {
+        match self.peek() {
+            Some(Token::Word(word)) => format!("`{}`", word),
+            Some(Token::Symbol(symbol)) => format!("`{}`", symbol),
+            None => String::from("the end of the text"),
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: char) -> Result<(), StickfigureError> {
+        if self.peek() == Some(Token::Symbol(symbol)) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(format!("Expected `{}`, but found {}.", symbol, self.describe_next())))
+        }
+    }
+
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute<'a>>, StickfigureError> {
+        let mut attributes = Vec::new();
+
+        while let Some(Token::Word(key)) = self.peek() {
+            if matches!(key, "node" | "polyfill") || key.starts_with('@') {
+                break;
+            }
+            let line = self.line();
+            self.position += 1;
+
+            let value = if self.peek() == Some(Token::Symbol('=')) {
+                self.position += 1;
+                self.parse_value()?
+            } else {
+                Value::Flag
+            };
+            attributes.push(Attribute { key, value, line });
+        }
+
+        Ok(attributes)
+    }
+
+    fn parse_value(&mut self) -> Result<Value<'a>, StickfigureError> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                self.position += 1;
+                Ok(Value::Word(word))
+            }
+            Some(Token::Symbol('[')) => {
+                self.position += 1;
+                let mut words = Vec::new();
+                while let Some(Token::Word(word)) = self.peek() {
+                    words.push(word);
+                    self.position += 1;
+                }
+                self.expect_symbol(']')?;
+                Ok(Value::List(words))
+            }
+            Some(Token::Symbol('(')) => {
+                self.position += 1;
+                let attributes = self.parse_attributes()?;
+                self.expect_symbol(')')?;
+                Ok(Value::Group(attributes))
+            }
+            _ => Err(self.error(format!("Expected a value, but found {}.", self.describe_next()))),
+        }
+    }
+
+    /// Parses a node and its children, after the `node` keyword.
+    fn parse_node(&mut self) -> Result<ParsedNode<'a>, StickfigureError> {
+        let line = self.line();
+        let name = match self.peek() {
+            Some(Token::Word(word)) if word.starts_with('@') => {
+                self.position += 1;
+                Some(word)
+            }
+            _ => None,
+        };
+        let attributes = self.parse_attributes()?;
+
+        let mut children = Vec::new();
+        if self.peek() == Some(Token::Symbol('{')) {
+            self.position += 1;
+            while self.peek() == Some(Token::Word("node")) {
+                self.position += 1;
+                children.push(self.parse_node()?);
+            }
+            self.expect_symbol('}')?;
+        }
+
+        Ok(ParsedNode {
+            name,
+            attributes,
+            children,
+            line,
+        })
+    }
+}
+
+fn parse_value<T: TextValue>(attribute: &Attribute) -> Result<T, StickfigureError> {
+    let invalid = |message: String| Err(StickfigureError::InvalidText(attribute.line, message));
+    match &attribute.value {
+        Value::Flag => T::from_word("true")
+            .map_or_else(|| invalid(format!("`{}` needs a value.", attribute.key)), Ok),
+        Value::Word(word) => {
+            T::from_word(word).map_or_else(|| invalid(format!("`{}` is not a valid value for `{}`.", word, attribute.key)), Ok)
+        }
+        Value::List(_) | Value::Group(_) => invalid(format!("`{}` needs a single value.", attribute.key)),
+    }
+}
+
+/// A node with everything but its references to other nodes resolved.
+struct FlatNode<'a> {
+    node: Node,
+    parent: Option<DrawOrderIndex>,
+    /// Name of the node the connector ends at, with the line it is on.
+    connector_end: Option<(&'a str, usize)>,
+}
+
+pub(crate) fn read_text(text: &str) -> Result<Stickfigure, LibraryError> {
+    let mut parser = Parser {
+        tokens: tokenize(text),
+        position: 0,
+    };
+
+    if parser.peek() != Some(Token::Word("stickfigure")) {
+        return Err(parser.error(format!("Expected `stickfigure`, but found {}.", parser.describe_next())).into());
+    }
+    parser.position += 1;
+    let header = parser.parse_attributes()?;
+
+    let mut root = None;
+    let mut polyfills = Vec::new();
+    while let Some(token) = parser.peek() {
+        match token {
+            Token::Word("node") if root.is_none() => {
+                parser.position += 1;
+                root = Some(parser.parse_node()?);
+            }
+            Token::Word("node") => return Err(parser.error(String::from("There can only be one root node.")).into()),
+            Token::Word("polyfill") => {
+                let line = parser.line();
+                parser.position += 1;
+                polyfills.push((parser.parse_attributes()?, line));
+            }
+            _ => {
+                return Err(parser
+                    .error(format!("Expected `node` or `polyfill`, but found {}.", parser.describe_next()))
+                    .into())
+            }
+        }
+    }
+    let Some(root) = root else {
+        return Err(parser.error(String::from("The stickfigure has no root node.")).into());
+    };
+
+    let mut stickfigure = Stickfigure::default();
+    for attribute in &header {
+        if !set_field!(attribute, stickfigure, version, build, scale, color) {
+            return Err(StickfigureError::InvalidText(
+                attribute.line,
+                format!("`{}` is not a property of a stickfigure.", attribute.key),
+            )
+            .into());
+        }
+    }
+    Stickfigure::check_writable_version(stickfigure.version, stickfigure.build)?;
+
+    let mut nodes: Vec<FlatNode> = Vec::new();
+    let mut names: HashMap<&str, DrawOrderIndex> = HashMap::new();
+    let explicit_draw_indices = root.attributes.iter().any(|attribute| attribute.key == "draw");
+    flatten(&root, None, explicit_draw_indices, &mut nodes, &mut names)?;
+
+    stickfigure.add_root_node();
+    for flat_node in &nodes {
+        match flat_node.parent {
+            None => {
+                if let Some(root_node) = stickfigure.get_node_mut(DrawOrderIndex(0)) {
+                    *root_node = Node {
+                        node_type: NodeType::RootNode,
+                        ..flat_node.node.clone()
+                    };
+                }
+            }
+            Some(parent) => {
+                stickfigure.add_node_at_unique_index(flat_node.node.clone(), parent, flat_node.node.draw_order_index)?;
+            }
+        }
+    }
+
+    let resolve = |reference: &str, line: usize| {
+        names
+            .get(reference)
+            .copied()
+            .ok_or_else(|| StickfigureError::InvalidText(line, format!("There is no node named `{}`.", reference)))
+    };
+
+    for flat_node in &nodes {
+        if let Some((end, line)) = flat_node.connector_end {
+            let end = resolve(end, line)?;
+            if let Some(connector_data) = stickfigure
+                .get_node_mut(flat_node.node.draw_order_index)
+                .and_then(|node| node.connector_data.as_mut())
+            {
+                connector_data.end_node_draw_index = end;
+            }
+        }
+    }
+
+    for (attributes, line) in polyfills {
+        let mut polyfill = Polyfill::default();
+        let mut anchor = None;
+        for attribute in &attributes {
+            match (attribute.key, &attribute.value) {
+                ("anchor", Value::Word(reference)) => anchor = Some(resolve(reference, attribute.line)?),
+                ("attached", Value::List(references)) => {
+                    polyfill.attached_node_draw_indices = references
+                        .iter()
+                        .map(|reference| resolve(reference, attribute.line))
+                        .collect::<Result<_, _>>()?;
+                }
+                ("anchor" | "attached", _) => {
+                    return Err(StickfigureError::InvalidText(
+                        attribute.line,
+                        format!("`{}` needs a node name, or a list of them in `[]`.", attribute.key),
+                    )
+                    .into())
+                }
+                _ => {
+                    if !set_field!(attribute, polyfill, color, use_polyfill_color) {
+                        return Err(StickfigureError::InvalidText(
+                            attribute.line,
+                            format!("`{}` is not a property of a polyfill.", attribute.key),
+                        )
+                        .into());
+                    }
+                }
+            }
+        }
+        polyfill.anchor_node_draw_index =
+            anchor.ok_or_else(|| StickfigureError::InvalidText(line, String::from("A polyfill needs an `anchor`.")))?;
+        stickfigure.polyfills.push(polyfill);
+    }
+
+    stickfigure.check_capabilities()?;
+
+    Ok(stickfigure)
+}
+
+/// Resolves the properties of `parsed` and its descendants, appending them to `nodes` parents first.
+///
+/// Nodes take their draw order index from `draw` if `explicit_draw_indices`, or else from the order they appear in.
+fn flatten<'a>(
+    parsed: &ParsedNode<'a>,
+    parent: Option<DrawOrderIndex>,
+    explicit_draw_indices: bool,
+    nodes: &mut Vec<FlatNode<'a>>,
+    names: &mut HashMap<&'a str, DrawOrderIndex>,
+) -> Result<(), StickfigureError> {
+    let mut node = if parent.is_none() {
+        Node {
+            node_type: NodeType::RootNode,
+            ..Node::default()
+        }
+    } else {
+        Node::default()
+    };
+    let mut draw_index = None;
+    let mut connector_end = None;
+
+    for attribute in &parsed.attributes {
+        match attribute.key {
+            "draw" => draw_index = Some(DrawOrderIndex(parse_value(attribute)?)),
+            "connector" => {
+                let Value::Group(connector_attributes) = &attribute.value else {
+                    return Err(StickfigureError::InvalidText(
+                        attribute.line,
+                        String::from("`connector` needs its properties in `()`."),
+                    ));
+                };
+                let mut connector_data = ConnectorData::default();
+                for connector_attribute in connector_attributes {
+                    match (connector_attribute.key, &connector_attribute.value) {
+                        ("end", Value::Word(reference)) => connector_end = Some((*reference, connector_attribute.line)),
+                        _ => {
+                            if !with_connector_fields!(set_field!(connector_attribute, connector_data)) {
+                                return Err(StickfigureError::InvalidText(
+                                    connector_attribute.line,
+                                    format!("`{}` is not a property of a connector.", connector_attribute.key),
+                                ));
+                            }
+                        }
+                    }
+                }
+                if connector_end.is_none() {
+                    return Err(StickfigureError::InvalidText(
+                        attribute.line,
+                        String::from("A connector needs an `end`."),
+                    ));
+                }
+                node.connector_data = Some(connector_data);
+            }
+            _ => {
+                if !with_node_fields!(set_field!(attribute, node)) {
+                    return Err(StickfigureError::InvalidText(
+                        attribute.line,
+                        format!("`{}` is not a property of a node.", attribute.key),
+                    ));
+                }
+            }
+        }
+    }
+
+    node.draw_order_index = match (draw_index, explicit_draw_indices) {
+        (Some(_), false) | (None, true) => {
+            return Err(StickfigureError::InvalidText(
+                parsed.line,
+                String::from("Either every node or no node must have `draw`."),
+            ))
+        }
+        (Some(draw_index), true) => draw_index,
+        (None, false) => DrawOrderIndex(nodes.len() as i32),
+    };
+    let draw_order_index = node.draw_order_index;
+    if parent.is_none() {
+        if draw_order_index != DrawOrderIndex(0) {
+            return Err(StickfigureError::InvalidText(
+                parsed.line,
+                format!("The root node must have `draw=0`, not `draw={}`.", draw_order_index.0),
+            ));
+        }
+    } else if nodes.iter().any(|existing| existing.node.draw_order_index == draw_order_index) {
+        return Err(StickfigureError::InvalidText(
+            parsed.line,
+            format!("Another node already has `draw={}`.", draw_order_index.0),
+        ));
+    }
+
+    if let Some(name) = parsed.name {
+        if names.insert(name, draw_order_index).is_some() {
+            return Err(StickfigureError::InvalidText(
+                parsed.line,
+                format!("Another node is already named `{}`.", name),
+            ));
+        }
+    }
+
+    nodes.push(FlatNode {
+        node,
+        parent,
+        connector_end,
+    });
+    for child in &parsed.children {
+        flatten(child, Some(draw_order_index), explicit_draw_indices, nodes, names)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+
+    /// Reads back the text of `stickfigure` and checks that nothing was lost.
+    fn assert_round_trips(stickfigure: &Stickfigure) -> Stickfigure {
+        let text = stickfigure.to_text();
+        let read_back = Stickfigure::from_text(&text).unwrap();
+
+        assert_eq!(read_back.to_text(), text);
+        assert_eq!(read_back.to_bytes().unwrap(), stickfigure.to_bytes().unwrap(), "text:\n{text}");
+        read_back
+    }
+
+    #[test]
+    fn round_trips_nodes_in_preorder() {
+        let mut stickfigure = Stickfigure::new();
+        let arm = stickfigure.add_node(segment(60.0, 90.0), DrawOrderIndex(0)).unwrap();
+        stickfigure.add_node(segment(30.0, -45.0), arm).unwrap();
+        stickfigure.add_node(segment(80.0, 180.0), DrawOrderIndex(0)).unwrap();
+
+        let text = stickfigure.to_text();
+        assert!(!text.contains("draw="));
+        assert_round_trips(&stickfigure);
+    }
+
+    #[test]
+    fn round_trips_polyfills_connectors_and_draw_order() {
+        let mut stickfigure = Stickfigure::new();
+        let arm = stickfigure.add_node(segment(60.0, 90.0), DrawOrderIndex(0)).unwrap();
+        let hand = stickfigure.add_node(segment(30.0, -45.0), arm).unwrap();
+        let tail = stickfigure
+            .add_node(
+                Node::from_options(NodeOptions {
+                    node_type: NodeType::Trapezoid,
+                    length: 40.0,
+                    ..Default::default()
+                }),
+                DrawOrderIndex(0),
+            )
+            .unwrap();
+        stickfigure
+            .update_node(tail, |node| node.trapezoid_top_thickness_ratio = 0.25)
+            .unwrap();
+        let connector = stickfigure
+            .add_connector(
+                segment(10.0, 0.0),
+                hand,
+                tail,
+                ConnectorData {
+                    percent: 0.5,
+                    local_x: 2.0,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        stickfigure.add_polyfill(Polyfill {
+            anchor_node_draw_index: arm,
+            attached_node_draw_indices: Vec::from([arm, hand, tail]),
+            color: Color::from_rgba(255, 204, 0, 255),
+            use_polyfill_color: true,
+        });
+        // Draw the connector before its parent, so the draw order is no longer the order the nodes are nested in.
+        stickfigure.change_draw_index(connector, DrawOrderIndex(1)).unwrap();
+
+        let text = stickfigure.to_text();
+        assert!(text.contains("draw="));
+        assert!(text.contains("trapezoid_top_thickness_ratio=0.25"));
+
+        let read_back = assert_round_trips(&stickfigure);
+        assert!(read_back.get_node(DrawOrderIndex(1)).unwrap().connector_data.is_some());
+        let tail = read_back.get_node(DrawOrderIndex(4)).unwrap();
+        assert_eq!(tail.node_type, NodeType::Trapezoid);
+        assert_eq!(tail.trapezoid_top_thickness_ratio, 0.25);
+        assert_eq!(
+            read_back.polyfills[0].attached_node_draw_indices,
+            stickfigure.polyfills[0].attached_node_draw_indices
+        );
+    }
+
+    #[test]
+    fn refuses_versions_and_features_that_set_version_and_build_would() {
+        let too_old = format!("stickfigure version={}\n\nnode\n", MIN_WRITABLE_VERSION - 1);
+        assert!(matches!(Stickfigure::from_text(&too_old), Err(LibraryError::UnsupportedVersion(_))));
+
+        let unsupported = "stickfigure version=403 build=40\n\nnode {\n    node is_floaty\n}\n";
+        assert!(matches!(
+            Stickfigure::from_text(unsupported),
+            Err(LibraryError::StickfigureError(StickfigureError::UnsupportedFeature(..)))
+        ));
+        assert!(Stickfigure::from_text(&unsupported.replace("build=40", "build=48")).is_ok());
+    }
+}
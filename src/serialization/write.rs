@@ -1,13 +1,11 @@
-use core::cell::RefCell;
-
 use byteorder::{BigEndian, WriteBytesExt};
 use core2::io::{Cursor, Write};
 extern crate alloc;
-use alloc::{format, rc::Rc, vec, vec::Vec};
+use alloc::{format, vec, vec::Vec};
 
 use crate::{
     error::*,
-    structs::{node::*, polyfill::*, stickfigure::*}, ConnectorMethod,
+    structs::{node::*, polyfill::*, stickfigure::*}, ConnectorMethod, Pose,
 };
 
 fn write_stickfigure_header(stickfigure: &Stickfigure) -> Result<Vec<u8>, StickfigureError> {
@@ -57,6 +55,7 @@ pub fn write_stickfigure(stickfigure: &Stickfigure) -> Result<Vec<u8>, LibraryEr
         stickfigure.build,
         DrawOrderIndex(0),
         stickfigure,
+        &stickfigure.compute_pose(),
     )?);
 
     if stickfigure.version >= 230 {
@@ -73,7 +72,7 @@ pub fn write_stickfigure(stickfigure: &Stickfigure) -> Result<Vec<u8>, LibraryEr
 fn write_connector_data(stickfigure: &Stickfigure) -> Result<Vec<u8>, StickfigureError> {
     let mut byte_vec = Vec::new();
 
-    let connector_nodes = stickfigure.get_nodes_with_property(|node| node.borrow().connector_data.is_some());
+    let connector_nodes = stickfigure.get_nodes_with_property(|node| node.connector_data.is_some());
 
     let mut buffer = [0u8; 4]; // fixed-size buffer. should be set to max size of bytes written in this section.
     let mut cursor = Cursor::new(&mut buffer[..]);
@@ -87,10 +86,10 @@ fn write_connector_data(stickfigure: &Stickfigure) -> Result<Vec<u8>, Stickfigur
         let mut buffer_: [u8; 8] = [0u8; 8]; // fixed-size buffer. should be set to max size of bytes written in this section.
         let mut cursor_ = Cursor::new(&mut buffer_[..]);
         cursor_
-            .write_i32::<BigEndian>(connector_node.borrow().get_draw_order_index().0)
+            .write_i32::<BigEndian>(connector_node.get_draw_order_index().0)
             .or_else(|err| return Err(StickfigureError::Io(err)))?;
 
-        let connector_data= &mut connector_node.borrow_mut().connector_data;
+        let connector_data = &connector_node.connector_data;
 
         match connector_data {
             Some(data) => {
@@ -112,6 +111,7 @@ fn write_child_nodes(
     build: i32,
     draw_index: DrawOrderIndex,
     stickfigure: &Stickfigure,
+    pose: &Pose,
 ) -> Result<Vec<u8>, StickfigureError> {
     let mut byte_vec = Vec::new();
 
@@ -125,7 +125,7 @@ fn write_child_nodes(
     let node_index = stickfigure.node_index_from_draw_order(draw_index);
 
     if let Some(node) = stickfigure.nodes.node_weight(node_index) {
-        byte_vec.append(&mut write_node(version, build, node, stickfigure)?);
+        byte_vec.append(&mut write_node(version, build, node, stickfigure, pose)?);
 
         let children = stickfigure.get_children(draw_index);
         let number_of_child_nodes = children.len() as i32;
@@ -140,7 +140,7 @@ fn write_child_nodes(
         if version >= 403 && build > 38 {
             for draw_index in &children {
                 if let Some(node) = stickfigure.get_node(*draw_index) {
-                    let connector_data_present = node.borrow().connector_data.is_some();
+                    let connector_data_present = node.connector_data.is_some();
                     let mut buffer = [0u8; 4]; // fixed-size buffer. should be set to max size of bytes written in this section.
                     let mut cursor = Cursor::new(&mut buffer[..]);
                     cursor
@@ -159,6 +159,7 @@ fn write_child_nodes(
                 build,
                 *child_draw_index,
                 stickfigure,
+                pose,
             )?);
         }
     }
@@ -169,28 +170,26 @@ fn write_child_nodes(
 fn write_node(
     version: i32,
     build: i32,
-    rc_node: &Rc<RefCell<Node>>,
-    stickfigure: &Stickfigure
+    node: &Node,
+    stickfigure: &Stickfigure,
+    pose: &Pose,
 ) -> Result<Vec<u8>, StickfigureError> {
-    {
-        let mut node = rc_node.borrow_mut();
+    let global_angle = |draw_index: DrawOrderIndex| pose.get(draw_index).map_or(0.0, |transform| transform.angle);
 
-        node.local_x = node.get_local_x(stickfigure);
-        node.local_y = node.get_local_y(stickfigure);
+    let node = {
+        let mut node = node.clone();
 
-        match node.triangle_type {
-            TriangleType::Isosceles => {
-                node.right_triangle_direction = 0;
-            },
-            TriangleType::RightTriangle => {
-                if node.triangle_flipped {
-                    node.right_triangle_direction = -1;
-                } else {
-                    node.right_triangle_direction = 1;
-                }
-            },
+        if node.node_type != NodeType::RootNode {
+            let angle_radians = global_angle(node.get_draw_order_index()).to_radians();
+            node.local_x = node.length * libm::cosf(angle_radians);
+            node.local_y = node.length * libm::sinf(angle_radians);
+        } else {
+            node.local_x = 0.0;
+            node.local_y = 0.0;
         }
 
+        node.right_triangle_direction = node.resolved_right_triangle_direction();
+
         if node.node_type.to_integer() == NodeType::RootNode.to_integer() {
             node.is_angle_locked = false;
             node.angle_lock_is_main_node = false;
@@ -204,30 +203,11 @@ fn write_node(
             if let Some(parent_node_draw_index) = stickfigure.get_parent(node.get_draw_order_index()) {
                 if let Some(parent_node) = stickfigure.get_node(parent_node_draw_index) {
                     if let Some(root_node) = stickfigure.get_node(DrawOrderIndex(0)) {
-                        match node.angle_lock_mode {
-                            AngleLockMode::None => {
-                                node.is_angle_locked = false;
-                                node.angle_lock_is_main_node = false;
-                                node.angle_lock_relative_start = 0.0;
-                                node.angle_lock_stickfigure_start = 0.0;
-                            },
-                            AngleLockMode::Absolute => {
-                                node.is_angle_locked = true;
-                                node.angle_lock_is_main_node = true;
-                                node.angle_lock_relative_start = 0.0;
-                                node.angle_lock_stickfigure_start = 0.0;
-                            },
-                            AngleLockMode::Relative => {
-                                node.is_angle_locked = true;
-                                node.angle_lock_is_main_node = false;
-                                node.angle_lock_relative_start = parent_node.borrow().get_global_angle(stickfigure);
-                                node.angle_lock_stickfigure_start = root_node.borrow().local_angle;
-                            },
-                        }
-    
-                        node.angle_lock_offset_minuend = node.get_global_angle(stickfigure);
-                        node.angle_lock_offset_subtrahend = parent_node.borrow().get_global_angle(stickfigure);
-                        node.angle_lock_offset = node.angle_lock_offset_minuend - node.angle_lock_offset_subtrahend;
+                        node.update_angle_lock_properties(
+                            global_angle(node.get_draw_order_index()),
+                            global_angle(parent_node.get_draw_order_index()),
+                            root_node.local_angle,
+                        );
                         
                     } else {
                         return Err(StickfigureError::GenericError(format!("Failed to get parent node draw index when setting private properties (while writing .nodes).")));
@@ -240,13 +220,14 @@ fn write_node(
     
             }
         }
-    }
-    let node = rc_node.borrow();
+
+        node
+    };
 
     let mut byte_vec = Vec::new();
 
     if version >= 403 && build >= 38 {
-        if let Some(connector_data) = &rc_node.borrow().connector_data {
+        if let Some(connector_data) = &node.connector_data {
             let mut buffer_c1 = [0u8; 12]; // fixed-size buffer. should be set to max size of bytes written in this section.
             let mut cursor_c1 = Cursor::new(&mut buffer_c1[..]);
             cursor_c1
@@ -705,9 +686,7 @@ fn write_polyfill_header(stickfigure: &Stickfigure) -> Result<Vec<u8>, Stickfigu
     Ok(byte_vec)
 }
 
-fn write_polyfill(rc_polyfill: &Rc<RefCell<Polyfill>>) -> Result<Vec<u8>, StickfigureError> {
-    let polyfill = rc_polyfill.borrow();
-
+fn write_polyfill(polyfill: &Polyfill) -> Result<Vec<u8>, StickfigureError> {
     let mut byte_vec = Vec::new();
 
     let mut buffer = [0u8; 13]; // fixed-size buffer. should be set to max size of bytes written in this section.
@@ -0,0 +1,188 @@
+extern crate alloc;
+use alloc::{format, string::ToString, vec::Vec};
+
+use crate::{DrawOrderIndex, PoseSnapshot, Stickfigure, StickfigureError};
+
+/// How the time between two keyframes is mapped to the progress from one to the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// Holds the first keyframe until the next one is reached.
+    Step,
+    /// Constant speed.
+    #[default]
+    Linear,
+    /// Starts slowly and speeds up.
+    EaseIn,
+    /// Starts quickly and slows down.
+    EaseOut,
+    /// Starts and ends slowly.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps linear progress `t`, from 0 to 1, to eased progress from 0 to 1. Eased curves are cubic.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Step => {
+                if t >= 1.0 { 1.0 } else { 0.0 }
+            }
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - 4.0 * (1.0 - t) * (1.0 - t) * (1.0 - t)
+                }
+            }
+        }
+    }
+}
+
+/// How angles are interpolated between keyframes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AngleInterpolation {
+    /// Interpolates the angle values as they are, so going from 0 to 720 spins the node twice.
+    Linear,
+    /// Turns the shorter way round, so going from 350 to 10 turns 20 degrees rather than -340.
+    #[default]
+    ShortestArc,
+}
+
+/// A pose at a point in time of an `Animation`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe {
+    /// Time of the keyframe, in seconds from the start of the animation.
+    pub time: f32,
+    pub pose: PoseSnapshot,
+    /// Easing of the transition from this keyframe to the next.
+    pub easing: Easing,
+}
+
+/// A timeline of keyframes posing one stickfigure.
+///
+/// Keyframes don't need to include every node: a node missing from a keyframe takes its properties from the stickfigure.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    stickfigure: Stickfigure,
+    keyframes: Vec<Keyframe>,
+    pub angle_interpolation: AngleInterpolation,
+}
+
+impl Animation {
+    /// Creates an animation of `stickfigure` with no keyframes.
+    pub fn new(stickfigure: Stickfigure) -> Self {
+        Self {
+            stickfigure,
+            keyframes: Vec::new(),
+            angle_interpolation: AngleInterpolation::default(),
+        }
+    }
+
+    pub fn stickfigure(&self) -> &Stickfigure {
+        &self.stickfigure
+    }
+
+    /// Gets the keyframes, sorted by time.
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /// Gets the time of the last keyframe, or 0 if there are none.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    /// Adds a keyframe, replacing any keyframe at the same time.
+    ///
+    /// # Parameters
+    ///
+    /// * `time` - Time of the keyframe in seconds. Must be finite and not negative.
+    /// * `pose` - Properties of the nodes at that time. Every draw order index in it must exist in the stickfigure.
+    /// * `easing` - Easing of the transition from this keyframe to the next.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StickfigureError::InvalidKeyframe` if `time` is invalid, or `StickfigureError::InvalidDrawIndices` if `pose`
+    /// has nodes that the stickfigure doesn't.
+    pub fn add_keyframe(&mut self, time: f32, pose: PoseSnapshot, easing: Easing) -> Result<(), StickfigureError> {
+        if !time.is_finite() || time < 0.0 {
+            return Err(StickfigureError::InvalidKeyframe(format!(
+                "Keyframe time {} must be finite and not negative.",
+                time
+            )));
+        }
+
+        let draw_indices: Vec<DrawOrderIndex> = pose.nodes.keys().copied().collect();
+        let missing_indices = self.stickfigure.missing_draw_indices(&draw_indices);
+        if !missing_indices.is_empty() {
+            return Err(StickfigureError::InvalidDrawIndices(
+                format!("{:?}", missing_indices),
+                "Cannot add keyframe with nodes that are not in the animated stickfigure.".to_string(),
+            ));
+        }
+
+        let keyframe = Keyframe { time, pose, easing };
+        match self.keyframes.binary_search_by(|existing| existing.time.total_cmp(&time)) {
+            Ok(i) => self.keyframes[i] = keyframe,
+            Err(i) => self.keyframes.insert(i, keyframe),
+        }
+        Ok(())
+    }
+
+    /// Removes the keyframe at `time`, if there is one.
+    pub fn remove_keyframe(&mut self, time: f32) -> Option<Keyframe> {
+        let i = self.keyframes.iter().position(|keyframe| keyframe.time == time)?;
+        Some(self.keyframes.remove(i))
+    }
+
+    /// Gets the pose of every node at `time`.
+    ///
+    /// Between two keyframes, each node's properties are interpolated with the easing of the earlier keyframe and the
+    /// animation's `angle_interpolation`. Before the first keyframe and after the last, the nearest keyframe is held.
+    /// With no keyframes, the stickfigure's own pose is returned.
+    pub fn sample(&self, time: f32) -> PoseSnapshot {
+        let mut pose = PoseSnapshot::capture(&self.stickfigure);
+
+        let next = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+        let (from, to) = match (next.checked_sub(1), self.keyframes.get(next)) {
+            (None, None) => return pose,
+            (None, Some(to)) => (to, to),
+            (Some(from), None) => (&self.keyframes[from], &self.keyframes[from]),
+            (Some(from), Some(to)) => (&self.keyframes[from], to),
+        };
+
+        let span = to.time - from.time;
+        let t = if span > 0.0 { from.easing.apply((time - from.time) / span) } else { 0.0 };
+
+        for (draw_index, state) in pose.nodes.iter_mut() {
+            let from_state = from.pose.get(*draw_index).unwrap_or(state);
+            let to_state = to.pose.get(*draw_index).unwrap_or(state);
+            *state = from_state.interpolate(to_state, t, self.angle_interpolation);
+        }
+        pose
+    }
+
+    /// Gets a copy of the stickfigure posed as it is at `time`. See `sample`.
+    pub fn frame_at(&self, time: f32) -> Stickfigure {
+        let mut stickfigure = self.stickfigure.clone();
+        self.sample(time).write_to(&mut stickfigure);
+        stickfigure
+    }
+
+    /// Samples the whole animation at a fixed frame rate, from time 0 to `duration` inclusive.
+    ///
+    /// Returns an empty list if `frames_per_second` is not positive and finite.
+    pub fn frames(&self, frames_per_second: f32) -> Vec<PoseSnapshot> {
+        if !frames_per_second.is_finite() || frames_per_second <= 0.0 {
+            return Vec::new();
+        }
+
+        let frame_count = libm::floorf(self.duration() * frames_per_second) as usize + 1;
+        (0..frame_count)
+            .map(|frame| self.sample(frame as f32 / frames_per_second))
+            .collect()
+    }
+}
@@ -0,0 +1,197 @@
+use glam::Vec2;
+
+use crate::render::scene::scene_bounds;
+use crate::{DrawOrderIndex, FillRule, Node, Pose, Stickfigure};
+
+/// An axis-aligned box, in the same coordinates as `NodeTransform`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Bounds {
+    /// Gets the smallest box containing every point, or `None` if there are none.
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vec2>) -> Option<Bounds> {
+        points.into_iter().fold(None, |bounds: Option<Bounds>, point| {
+            Some(match bounds {
+                None => Bounds { min: *point, max: *point },
+                Some(bounds) => Bounds {
+                    min: bounds.min.min(*point),
+                    max: bounds.max.max(*point),
+                },
+            })
+        })
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.0
+    }
+
+    /// Whether `point` is inside the box or on its edge.
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Gets the smallest box containing both boxes.
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Grows the box by `amount` on every side.
+    pub fn expand(&self, amount: f32) -> Bounds {
+        Bounds {
+            min: self.min - Vec2::splat(amount),
+            max: self.max + Vec2::splat(amount),
+        }
+    }
+}
+
+impl Node {
+    /// Gets the box around everything drawn for this node: its shape, with its thickness, and its circle outline if it has one.
+    ///
+    /// # Returns
+    ///
+    /// The node's `Bounds`, or `None` if it draws nothing (e.g. the root node) or is not in `pose`.
+    pub fn bounds(&self, pose: &Pose) -> Option<Bounds> {
+        let shape = self.shape(pose);
+        let circle_outline = self.circle_outline_shape(pose);
+        Bounds::from_points(
+            shape
+                .contours
+                .iter()
+                .chain(circle_outline.iter().flat_map(|outline| outline.contours.iter()))
+                .flatten(),
+        )
+    }
+}
+
+impl Stickfigure {
+    /// Gets the box around everything drawn for the stickfigure as currently posed, polyfills included.
+    ///
+    /// # Returns
+    ///
+    /// The stickfigure's `Bounds`, or `None` if it draws nothing.
+    pub fn bounds(&self) -> Option<Bounds> {
+        let items = self.scene(&self.compute_pose(), FillRule::default());
+        scene_bounds(&items).map(|(min, max)| Bounds { min, max })
+    }
+
+    /// Finds what is drawn at `point`, as currently posed. See `hit_test_pose`.
+    pub fn hit_test(&self, point: Vec2, tolerance: f32) -> Option<DrawOrderIndex> {
+        self.hit_test_pose(&self.compute_pose(), point, tolerance)
+    }
+
+    /// Finds the topmost node drawn at `point` in `pose`.
+    ///
+    /// Everything drawn is tested from front to back: node shapes, circle outlines and polyfills (with the nonzero fill rule).
+    /// A point hits a shape if it is inside it or within `tolerance` of its edge.
+    ///
+    /// # Parameters
+    ///
+    /// * `pose` - Positions of the nodes, from `compute_pose`.
+    /// * `point` - Point to test, in the same coordinates as `NodeTransform`.
+    /// * `tolerance` - How far outside a shape still counts as a hit, to make thin nodes easier to click.
+    ///
+    /// # Returns
+    ///
+    /// The `DrawOrderIndex` of the node hit, or of the anchor node if a polyfill was hit, or `None` if nothing is at `point`.
+    pub fn hit_test_pose(&self, pose: &Pose, point: Vec2, tolerance: f32) -> Option<DrawOrderIndex> {
+        let items = self.scene(pose, FillRule::default());
+        let tolerance = tolerance.max(0.0);
+
+        items
+            .iter()
+            .rev()
+            .find(|item| {
+                let in_reach = Bounds::from_points(item.shape.contours.iter().flatten())
+                    .is_some_and(|bounds| bounds.expand(tolerance).contains(point));
+                if !in_reach {
+                    return false;
+                }
+
+                item.shape.triangles.iter().any(|triangle| triangle_contains(triangle, point))
+                    || item.shape.contours.iter().any(|contour| {
+                        (0..contour.len()).any(|i| {
+                            distance_to_segment(point, contour[i], contour[(i + 1) % contour.len()]) <= tolerance
+                        })
+                    })
+            })
+            .map(|item| item.draw_index)
+    }
+}
+
+/// Whether `point` is inside `triangle` or on its edge, whichever way round its corners go.
+pub(crate) fn triangle_contains([a, b, c]: &[Vec2; 3], point: Vec2) -> bool {
+    let d1 = (*b - *a).perp_dot(point - *a);
+    let d2 = (*c - *b).perp_dot(point - *b);
+    let d3 = (*a - *c).perp_dot(point - *c);
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_negative && has_positive)
+}
+
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let span = end - start;
+    let t = if span.length_squared() > 0.0 {
+        ((point - start).dot(span) / span.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance(start + span * t)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+    use alloc::vec;
+
+    use super::*;
+    use crate::test_util::segment;
+    use crate::Polyfill;
+
+    /// Adds a node of length 100 and thickness 4 to the root node.
+    fn add_limb(stickfigure: &mut Stickfigure, local_angle: f32) -> DrawOrderIndex {
+        let mut node = segment(100.0, local_angle);
+        node.thickness = 4;
+        stickfigure.add_node(node, DrawOrderIndex(0)).unwrap()
+    }
+
+    #[test]
+    fn hit_test_picks_the_topmost_node_within_tolerance() {
+        let mut stickfigure = Stickfigure::new();
+        add_limb(&mut stickfigure, 0.0);
+        let on_top = add_limb(&mut stickfigure, 0.0);
+
+        assert_eq!(stickfigure.hit_test(Vec2::new(50.0, 0.0), 0.0), Some(on_top));
+        assert_eq!(stickfigure.hit_test(Vec2::new(50.0, 10.0), 5.0), None);
+        assert_eq!(stickfigure.hit_test(Vec2::new(50.0, 10.0), 10.0), Some(on_top));
+        assert_eq!(stickfigure.hit_test(Vec2::new(150.0, 0.0), 10.0), None);
+    }
+
+    #[test]
+    fn hit_test_counts_polyfills_as_their_anchor_node() {
+        let mut stickfigure = Stickfigure::new();
+        let along_x = add_limb(&mut stickfigure, 0.0);
+        let along_y = add_limb(&mut stickfigure, 90.0);
+        // The triangle between the two nodes and the root node, drawn above `along_x`.
+        stickfigure.add_polyfill(Polyfill {
+            anchor_node_draw_index: along_y,
+            attached_node_draw_indices: vec![along_x, DrawOrderIndex(0)],
+            ..Default::default()
+        });
+        let pose = stickfigure.compute_pose();
+
+        assert_eq!(stickfigure.hit_test_pose(&pose, Vec2::new(30.0, 30.0), 0.0), Some(along_y));
+        assert_eq!(stickfigure.hit_test_pose(&pose, Vec2::new(50.0, 1.0), 0.0), Some(along_y));
+        assert_eq!(stickfigure.hit_test_pose(&pose, Vec2::new(50.0, -1.0), 0.0), Some(along_x));
+        assert_eq!(stickfigure.hit_test_pose(&pose, Vec2::new(80.0, 80.0), 0.0), None);
+    }
+}
@@ -0,0 +1,250 @@
+extern crate alloc;
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::{AngleLockMode, GradientMode, Node, NodeType, StickfigureError};
+
+/// Maximum number of nodes a stickfigure can have.
+///
+/// This is the limit of current Stick Nodes releases. The `.nodes` format stores node counts the same way in every
+/// version, and no lower limit is known for older releases, so it applies to all of them.
+pub const NODE_LIMIT: usize = 400;
+
+/// What a specific Stick Nodes version and build can load.
+///
+/// The table is derived from the `.nodes` file format: a feature is available from the first
+/// version and build whose files store the properties it needs, since older apps can't read them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capabilities {
+    pub version: i32,
+    pub build: i32,
+    /// Maximum number of nodes. Currently `NODE_LIMIT` for every version and build.
+    pub max_node_count: usize,
+    pub node_types: Vec<NodeType>,
+    pub polyfills: bool,
+    pub connectors: bool,
+    pub gradients: bool,
+    /// Whether `GradientMode::Sideways` is available. `GradientMode::Normal` is available whenever gradients are.
+    pub gradient_modes: bool,
+    pub circle_outlines: bool,
+    pub hollow_circles: bool,
+    pub segment_curves: bool,
+    /// Whether trapezoids have separate start and end thicknesses, rather than a single top thickness ratio.
+    pub trapezoid_start_end_thickness: bool,
+    pub smart_stretch: bool,
+    /// Whether nodes can opt out of smart stretch with `do_not_apply_smart_stretch`, which was stored after smart stretch itself.
+    pub smart_stretch_opt_out: bool,
+    pub floaty: bool,
+    pub angle_lock: bool,
+    pub drag_lock: bool,
+}
+
+impl Capabilities {
+    /// Looks up the capabilities of a Stick Nodes version and build.
+    ///
+    /// The build number only exists from version 403 (4.0.3) onwards; it is ignored for older versions.
+    pub fn for_version(version: i32, build: i32) -> Self {
+        let since = |since_version: i32| version >= since_version;
+        let since_build = |since_build: i32| version >= 403 && build >= since_build;
+
+        let mut node_types = vec![
+            NodeType::RootNode,
+            NodeType::RoundedSegment,
+            NodeType::Segment,
+            NodeType::Circle,
+            NodeType::FilledCircle,
+        ];
+        if since(256) {
+            node_types.extend([
+                NodeType::Triangle,
+                NodeType::Ellipse,
+                NodeType::Trapezoid,
+                NodeType::Polygon,
+            ]);
+        }
+
+        Self {
+            version,
+            build,
+            max_node_count: NODE_LIMIT,
+            node_types,
+            polyfills: since(230),
+            connectors: since_build(39),
+            gradients: since(176),
+            gradient_modes: since_build(20),
+            circle_outlines: since(256),
+            hollow_circles: since_build(21),
+            segment_curves: since(320),
+            trapezoid_start_end_thickness: since_build(36),
+            smart_stretch: since(248),
+            smart_stretch_opt_out: since(252),
+            floaty: since_build(48),
+            angle_lock: since_build(51),
+            drag_lock: since_build(39),
+        }
+    }
+
+    pub fn supports_node_type(&self, node_type: &NodeType) -> bool {
+        self.node_types.contains(node_type)
+    }
+
+    /// Checks that a node only uses features these capabilities include.
+    ///
+    /// # Returns
+    ///
+    /// Result of `()`, or a `StickfigureError::UnsupportedFeature` naming the first unsupported feature found.
+    pub fn check_node(&self, node: &Node) -> Result<(), StickfigureError> {
+        let unsupported = |feature: &str| {
+            Err(StickfigureError::UnsupportedFeature(
+                String::from(feature),
+                self.version,
+                self.build,
+            ))
+        };
+
+        if !self.supports_node_type(&node.node_type) {
+            return unsupported(match node.node_type {
+                NodeType::Triangle => "Triangle node",
+                NodeType::Ellipse => "Ellipse node",
+                NodeType::Trapezoid => "Trapezoid node",
+                NodeType::Polygon => "Polygon node",
+                _ => "Node type",
+            });
+        }
+        if node.connector_data.is_some() && !self.connectors {
+            return unsupported("Connector node");
+        }
+        if node.use_gradient && !self.gradients {
+            return unsupported("Gradient");
+        }
+        if node.use_gradient
+            && matches!(node.gradient_mode, GradientMode::Sideways)
+            && !self.gradient_modes
+        {
+            return unsupported("Sideways gradient mode");
+        }
+        if node.use_circle_outline && !self.circle_outlines {
+            return unsupported("Circle outline");
+        }
+        if node.circle_is_hollow && !self.hollow_circles {
+            return unsupported("Hollow circle");
+        }
+        if node.node_type == NodeType::Trapezoid
+            && (node.use_trapezoid_thickness_start || node.use_trapezoid_thickness_end)
+            && !self.trapezoid_start_end_thickness
+        {
+            return unsupported("Trapezoid start and end thickness");
+        }
+        if node.segment_curve_radius_and_default_curve_radius != 0 && !self.segment_curves {
+            return unsupported("Segment curve");
+        }
+        if node.is_smart_stretch && !self.smart_stretch {
+            return unsupported("Smart stretch");
+        }
+        if node.do_not_apply_smart_stretch && !self.smart_stretch_opt_out {
+            return unsupported("Opting out of smart stretch");
+        }
+        if node.is_floaty && !self.floaty {
+            return unsupported("Floaty node");
+        }
+        if !matches!(node.angle_lock_mode, AngleLockMode::None) && !self.angle_lock {
+            return unsupported("Angle lock");
+        }
+        if node.is_drag_locked && !self.drag_lock {
+            return unsupported("Drag lock");
+        }
+
+        Ok(())
+    }
+
+    /// Checks that adding nodes would not exceed `max_node_count`.
+    pub fn check_node_count(
+        &self,
+        current_node_count: usize,
+        number_of_nodes_being_added: usize,
+    ) -> Result<(), StickfigureError> {
+        if current_node_count + number_of_nodes_being_added > self.max_node_count {
+            return Err(StickfigureError::NodeLimitError(
+                number_of_nodes_being_added,
+                current_node_count,
+                self.max_node_count,
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawOrderIndex, Stickfigure};
+
+    #[test]
+    fn smart_stretch_opt_out_needs_the_version_that_stores_it() {
+        let mut node = Node::new();
+        node.do_not_apply_smart_stretch = true;
+
+        assert!(Capabilities::for_version(248, 0).check_node(&node).is_err());
+        assert!(Capabilities::for_version(252, 0).check_node(&node).is_ok());
+
+        node.do_not_apply_smart_stretch = false;
+        node.is_smart_stretch = true;
+        assert!(Capabilities::for_version(248, 0).check_node(&node).is_ok());
+    }
+
+    #[test]
+    fn trapezoid_start_and_end_thickness_needs_build_36() {
+        let mut node = Node::new();
+        node.node_type = NodeType::Trapezoid;
+
+        assert!(Capabilities::for_version(403, 35).check_node(&node).is_err());
+        assert!(Capabilities::for_version(403, 36).check_node(&node).is_ok());
+
+        node.use_trapezoid_thickness_start = false;
+        node.use_trapezoid_thickness_end = false;
+        assert!(Capabilities::for_version(403, 35).check_node(&node).is_ok());
+    }
+
+    #[test]
+    fn older_versions_refuse_trapezoids_with_start_and_end_thickness() {
+        let mut stickfigure = Stickfigure::new();
+        let draw_index = stickfigure.add_node(Node::new(), DrawOrderIndex(0)).unwrap();
+        stickfigure
+            .update_node(draw_index, |node| node.node_type = NodeType::Trapezoid)
+            .unwrap();
+
+        assert!(stickfigure.set_version_and_build(403, 35).is_err());
+
+        stickfigure
+            .update_node(draw_index, |node| {
+                node.use_trapezoid_thickness_start = false;
+                node.use_trapezoid_thickness_end = false;
+            })
+            .unwrap();
+        stickfigure.set_version_and_build(403, 35).unwrap();
+        let read_back = Stickfigure::from_bytes(stickfigure.to_bytes().unwrap()).unwrap();
+        assert!(!read_back.get_node(draw_index).unwrap().use_trapezoid_thickness_start);
+    }
+
+    #[test]
+    fn update_node_undoes_unsupported_edits() {
+        let mut stickfigure = Stickfigure::from_version_and_build(250, 0).unwrap();
+        let draw_index = stickfigure.add_node(Node::new(), DrawOrderIndex(0)).unwrap();
+
+        assert!(stickfigure
+            .update_node(draw_index, |node| node.do_not_apply_smart_stretch = true)
+            .is_err());
+        assert!(!stickfigure.get_node(draw_index).unwrap().do_not_apply_smart_stretch);
+    }
+
+    #[test]
+    fn unsupported_edits_through_get_node_mut_are_refused_when_writing() {
+        let mut stickfigure = Stickfigure::from_version_and_build(250, 0).unwrap();
+        let draw_index = stickfigure.add_node(Node::new(), DrawOrderIndex(0)).unwrap();
+
+        stickfigure.get_node_mut(draw_index).unwrap().do_not_apply_smart_stretch = true;
+
+        assert!(stickfigure.check_capabilities().is_err());
+        assert!(stickfigure.to_bytes().is_err());
+    }
+}
@@ -2,21 +2,27 @@ use serde::{Deserialize, Serialize};
 
 use crate::DrawOrderIndex;
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ConnectorData {
+    /// Offset of the point the connector aims at from the end of the node it connects to, along the x axis.
     pub local_x: f32,
+    /// Offset of the point the connector aims at from the end of the node it connects to, along the y axis.
     pub local_y: f32,
     pub percent: f32,
     pub percent_default: f32,
     pub value: f32,
     pub method: ConnectorMethod,
     pub reversed: bool,
+    /// Multiplies `value` for `ConnectorMethod::ByValue`. Files from before build 65 don't store it and are read with
+    /// 1.0, which leaves `value` as it is. The default is 0.0, so set it when building a `ByValue` connector by hand.
     pub smart_stretch_ancestral_value: f32,
 
     pub end_node_draw_index: DrawOrderIndex
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum ConnectorMethod {
     #[default]
     ByPercent = 0,
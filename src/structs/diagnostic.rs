@@ -0,0 +1,529 @@
+use hashbrown::HashSet;
+extern crate alloc;
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{DrawOrderIndex, NodeType, Stickfigure, StickfigureError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Worth knowing about, but the app handles it fine.
+    Info,
+    /// The app will load the stickfigure, but it may not look or behave as intended.
+    Warning,
+    /// The written file is likely to be rejected by the app, or to crash it.
+    Error,
+}
+
+/// A single problem found by `Stickfigure::validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The node the problem was found on, if it is specific to one node.
+    pub draw_index: Option<DrawOrderIndex>,
+    pub message: String,
+    /// A change that resolves the problem, if one can be made automatically.
+    pub fix: Option<DiagnosticFix>,
+}
+
+/// An automatic fix for a `Diagnostic`. Apply it with `Stickfigure::apply_fix`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticFix {
+    /// Reassign contiguous draw order indices starting from 0, keeping the current order.
+    CompactDrawIndices,
+    SetNodeType {
+        draw_index: DrawOrderIndex,
+        node_type: NodeType,
+    },
+    RemovePolyfill {
+        anchor_draw_index: DrawOrderIndex,
+    },
+    DetachPolyfillNodes {
+        anchor_draw_index: DrawOrderIndex,
+        draw_indices: Vec<DrawOrderIndex>,
+    },
+    /// Turn a connector node back into a regular node by removing its `ConnectorData`.
+    RemoveConnector {
+        draw_index: DrawOrderIndex,
+    },
+    SetDoNotApplySmartStretch {
+        draw_index: DrawOrderIndex,
+        value: bool,
+    },
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, draw_index: Option<DrawOrderIndex>, message: String) -> Self {
+        Self {
+            severity,
+            draw_index,
+            message,
+            fix: None,
+        }
+    }
+
+    fn with_fix(mut self, fix: DiagnosticFix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => write!(f, "info"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.draw_index {
+            Some(draw_index) => write!(f, "{} (node {}): {}", self.severity, draw_index.0, self.message),
+            None => write!(f, "{}: {}", self.severity, self.message),
+        }
+    }
+}
+
+impl Stickfigure {
+    /// Checks the invariants that writing a `.nodes` file relies on, plus values the app doesn't accept.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec` of every problem found, most severe first. An empty `Vec` means the stickfigure is valid.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        self.validate_draw_indices(&mut diagnostics);
+        self.validate_root_node(&mut diagnostics);
+        self.validate_polyfills(&mut diagnostics);
+        self.validate_nodes(&mut diagnostics);
+
+        diagnostics.sort_by_key(|diagnostic| core::cmp::Reverse(diagnostic.severity));
+
+        diagnostics
+    }
+
+    /// Applies a single fix suggested by `validate`.
+    pub fn apply_fix(&mut self, fix: &DiagnosticFix) -> Result<(), StickfigureError> {
+        match fix {
+            DiagnosticFix::CompactDrawIndices => {
+                self.compact_draw_indices();
+            }
+            DiagnosticFix::SetNodeType {
+                draw_index,
+                node_type,
+            } => {
+                self.update_node(*draw_index, |node| node.node_type = node_type.clone())?;
+            }
+            DiagnosticFix::RemovePolyfill { anchor_draw_index } => {
+                self.remove_polyfill(*anchor_draw_index)?;
+            }
+            DiagnosticFix::DetachPolyfillNodes {
+                anchor_draw_index,
+                draw_indices,
+            } => {
+                self.update_polyfill(*anchor_draw_index, |polyfill, _| {
+                    polyfill
+                        .attached_node_draw_indices
+                        .retain(|attached| !draw_indices.contains(attached));
+                })?;
+            }
+            DiagnosticFix::RemoveConnector { draw_index } => {
+                self.update_node(*draw_index, |node| node.connector_data = None)?;
+            }
+            DiagnosticFix::SetDoNotApplySmartStretch { draw_index, value } => {
+                self.update_node(*draw_index, |node| node.do_not_apply_smart_stretch = *value)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Repeatedly validates and applies every available fix until no fixable problems remain.
+    ///
+    /// # Returns
+    ///
+    /// Result of the diagnostics that could not be fixed automatically.
+    pub fn apply_all_fixes(&mut self) -> Result<Vec<Diagnostic>, StickfigureError> {
+        // Fixing one problem can only ever remove or renumber things, so this settles quickly.
+        // The bound is a safeguard against a fix that doesn't resolve its own diagnostic.
+        for _ in 0..8 {
+            let fixes: Vec<DiagnosticFix> = self
+                .validate()
+                .into_iter()
+                .filter_map(|diagnostic| diagnostic.fix)
+                .collect();
+
+            if fixes.is_empty() {
+                break;
+            }
+
+            // Fixes that renumber draw indices invalidate the others, so apply them on their own.
+            if fixes.contains(&DiagnosticFix::CompactDrawIndices) {
+                self.apply_fix(&DiagnosticFix::CompactDrawIndices)?;
+                continue;
+            }
+
+            for fix in fixes.iter() {
+                self.apply_fix(fix)?;
+            }
+        }
+
+        Ok(self.validate())
+    }
+
+    fn validate_draw_indices(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let mut draw_indices = self.get_all_node_indices();
+        draw_indices.sort();
+
+        let is_contiguous = draw_indices
+            .iter()
+            .enumerate()
+            .all(|(position, draw_index)| draw_index.0 == position as i32);
+
+        if !is_contiguous {
+            diagnostics.push(
+                Diagnostic::new(
+                    Severity::Error,
+                    None,
+                    String::from("Draw order indices are not contiguous starting from 0."),
+                )
+                .with_fix(DiagnosticFix::CompactDrawIndices),
+            );
+        }
+
+        for draw_index in draw_indices {
+            if let Some(node) = self.get_node(draw_index) {
+                if node.get_draw_order_index() != draw_index {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            Severity::Error,
+                            Some(draw_index),
+                            format!(
+                                "Node stores draw order index {} but is registered at {}.",
+                                node.get_draw_order_index().0,
+                                draw_index.0
+                            ),
+                        )
+                        .with_fix(DiagnosticFix::CompactDrawIndices),
+                    );
+                }
+            }
+        }
+    }
+
+    fn validate_root_node(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let root_nodes = self.get_nodes_with_property(|node| node.node_type == NodeType::RootNode);
+
+        match self.get_node(DrawOrderIndex(0)) {
+            Some(node) if node.node_type != NodeType::RootNode => {
+                diagnostics.push(
+                    Diagnostic::new(
+                        Severity::Error,
+                        Some(DrawOrderIndex(0)),
+                        String::from("The node at draw order index 0 is not a root node."),
+                    )
+                    .with_fix(DiagnosticFix::SetNodeType {
+                        draw_index: DrawOrderIndex(0),
+                        node_type: NodeType::RootNode,
+                    }),
+                );
+            }
+            Some(_) => {}
+            None => {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    None,
+                    String::from("There is no node at draw order index 0."),
+                ));
+            }
+        }
+
+        for draw_index in root_nodes.into_iter().filter(|draw_index| draw_index.0 != 0) {
+            diagnostics.push(
+                Diagnostic::new(
+                    Severity::Error,
+                    Some(draw_index),
+                    String::from("Only the node at draw order index 0 may be a root node."),
+                )
+                .with_fix(DiagnosticFix::SetNodeType {
+                    draw_index,
+                    node_type: NodeType::RoundedSegment,
+                }),
+            );
+        }
+
+        for draw_index in self.get_all_node_indices() {
+            if draw_index.0 != 0 && self.get_parent(draw_index).is_none() {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    Some(draw_index),
+                    String::from("Node has no parent, so it is not connected to the root node."),
+                ));
+            }
+        }
+    }
+
+    fn validate_polyfills(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let mut anchors = HashSet::new();
+
+        for polyfill in self.polyfills.iter() {
+            let anchor_draw_index = polyfill.anchor_node_draw_index;
+
+            if !self.draw_index_exists(anchor_draw_index) {
+                diagnostics.push(
+                    Diagnostic::new(
+                        Severity::Error,
+                        Some(anchor_draw_index),
+                        String::from("Polyfill is anchored to a node that does not exist."),
+                    )
+                    .with_fix(DiagnosticFix::RemovePolyfill { anchor_draw_index }),
+                );
+                continue;
+            }
+
+            if !anchors.insert(anchor_draw_index) {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    Some(anchor_draw_index),
+                    String::from("More than one polyfill is anchored to this node."),
+                ));
+            }
+
+            let missing_draw_indices =
+                self.missing_draw_indices(&polyfill.attached_node_draw_indices);
+            if !missing_draw_indices.is_empty() {
+                diagnostics.push(
+                    Diagnostic::new(
+                        Severity::Error,
+                        Some(anchor_draw_index),
+                        format!(
+                            "Polyfill is attached to nodes that do not exist: {:?}",
+                            missing_draw_indices
+                        ),
+                    )
+                    .with_fix(DiagnosticFix::DetachPolyfillNodes {
+                        anchor_draw_index,
+                        draw_indices: missing_draw_indices,
+                    }),
+                );
+            }
+
+            if polyfill.attached_node_draw_indices.len() < 2 {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Info,
+                    Some(anchor_draw_index),
+                    String::from("Polyfill has fewer than three vertices, so it covers no area."),
+                ));
+            }
+        }
+    }
+
+    fn validate_nodes(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let capabilities = self.capabilities();
+
+        for draw_index in self.get_all_node_indices() {
+            let Some(node) = self.get_node(draw_index) else {
+                continue;
+            };
+
+            if let Err(err) = capabilities.check_node(node) {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    Some(draw_index),
+                    format!("{}", err),
+                ));
+            }
+
+            if let Some(connector_data) = &node.connector_data {
+                let end_node_draw_index = connector_data.end_node_draw_index;
+
+                if !self.draw_index_exists(end_node_draw_index) {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            Severity::Error,
+                            Some(draw_index),
+                            format!(
+                                "Connector ends at node {}, which does not exist.",
+                                end_node_draw_index.0
+                            ),
+                        )
+                        .with_fix(DiagnosticFix::RemoveConnector { draw_index }),
+                    );
+                } else if end_node_draw_index == draw_index {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            Severity::Error,
+                            Some(draw_index),
+                            String::from("Connector ends at itself."),
+                        )
+                        .with_fix(DiagnosticFix::RemoveConnector { draw_index }),
+                    );
+                } else if self.get_children_recursive(draw_index).contains(&end_node_draw_index) {
+                    diagnostics.push(
+                        Diagnostic::new(
+                            Severity::Warning,
+                            Some(draw_index),
+                            format!(
+                                "Connector ends at node {}, which is attached below it, so it is placed like an ordinary node.",
+                                end_node_draw_index.0
+                            ),
+                        )
+                        .with_fix(DiagnosticFix::RemoveConnector { draw_index }),
+                    );
+                }
+            }
+
+            if node.is_static && !node.do_not_apply_smart_stretch && capabilities.smart_stretch_opt_out {
+                diagnostics.push(
+                    Diagnostic::new(
+                        Severity::Warning,
+                        Some(draw_index),
+                        String::from("Static node does not have do_not_apply_smart_stretch set."),
+                    )
+                    .with_fix(DiagnosticFix::SetDoNotApplySmartStretch {
+                        draw_index,
+                        value: true,
+                    }),
+                );
+            }
+
+            // The range the app accepts for these is not known, so only values that can't be drawn as stored are flagged,
+            // and they are left for the user to fix.
+            if node.node_type == NodeType::Polygon && node.num_polygon_vertices < 3 {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    Some(draw_index),
+                    format!(
+                        "Polygon has {} vertices, so it has no area. Node shapes draw it as a triangle.",
+                        node.num_polygon_vertices
+                    ),
+                ));
+            }
+
+            if node.segment_curve_polyfill_precision < 1 {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    Some(draw_index),
+                    format!(
+                        "segment_curve_polyfill_precision is {}, so polyfills can't follow the curve. Polyfills use 1 instead.",
+                        node.segment_curve_polyfill_precision
+                    ),
+                ));
+            }
+
+            if node.thickness < 0 {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    Some(draw_index),
+                    format!("Thickness is negative ({}).", node.thickness),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Color, Node, Polyfill};
+    use alloc::vec;
+
+    fn segment() -> Node {
+        Node::new()
+    }
+
+    #[test]
+    fn new_stickfigure_is_valid() {
+        assert_eq!(Stickfigure::new().validate(), Vec::new());
+    }
+
+    #[test]
+    fn reports_gaps_in_draw_indices_and_compacts_them() {
+        let mut stickfigure = Stickfigure::new();
+        stickfigure.add_node(segment(), DrawOrderIndex(0)).unwrap();
+        stickfigure
+            .add_node_at_unique_index(segment(), DrawOrderIndex(1), DrawOrderIndex(5))
+            .unwrap();
+
+        let diagnostics = stickfigure.validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].fix, Some(DiagnosticFix::CompactDrawIndices));
+
+        assert_eq!(stickfigure.apply_all_fixes().unwrap(), Vec::new());
+        assert_eq!(stickfigure.get_parent(DrawOrderIndex(2)), Some(DrawOrderIndex(1)));
+    }
+
+    #[test]
+    fn fixes_polyfills_that_reference_missing_nodes() {
+        let mut stickfigure = Stickfigure::new();
+        let first = stickfigure.add_node(segment(), DrawOrderIndex(0)).unwrap();
+        let second = stickfigure.add_node(segment(), first).unwrap();
+        let third = stickfigure.add_node(segment(), second).unwrap();
+        stickfigure.add_polyfill(Polyfill {
+            anchor_node_draw_index: first,
+            color: Color::default(),
+            use_polyfill_color: false,
+            attached_node_draw_indices: vec![second, third, DrawOrderIndex(40)],
+        });
+        stickfigure.add_polyfill(Polyfill {
+            anchor_node_draw_index: DrawOrderIndex(50),
+            color: Color::default(),
+            use_polyfill_color: false,
+            attached_node_draw_indices: vec![first, second],
+        });
+
+        let fixes: Vec<DiagnosticFix> = stickfigure.validate().into_iter().filter_map(|diagnostic| diagnostic.fix).collect();
+        assert!(fixes.contains(&DiagnosticFix::RemovePolyfill {
+            anchor_draw_index: DrawOrderIndex(50)
+        }));
+        assert!(fixes.contains(&DiagnosticFix::DetachPolyfillNodes {
+            anchor_draw_index: first,
+            draw_indices: vec![DrawOrderIndex(40)],
+        }));
+
+        assert_eq!(stickfigure.apply_all_fixes().unwrap(), Vec::new());
+        assert_eq!(stickfigure.polyfills.len(), 1);
+        assert_eq!(stickfigure.polyfills[0].attached_node_draw_indices, vec![second, third]);
+    }
+
+    #[test]
+    fn fixes_static_nodes_without_do_not_apply_smart_stretch() {
+        let mut stickfigure = Stickfigure::new();
+        let mut node = segment();
+        node.is_static = true;
+        let draw_index = stickfigure.add_node(node, DrawOrderIndex(0)).unwrap();
+
+        let diagnostics = stickfigure.validate();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+
+        assert_eq!(stickfigure.apply_all_fixes().unwrap(), Vec::new());
+        assert!(stickfigure.get_node(draw_index).unwrap().do_not_apply_smart_stretch);
+    }
+
+    #[test]
+    fn leaves_undrawable_values_for_the_user() {
+        let mut stickfigure = Stickfigure::new();
+        let mut node = segment();
+        node.node_type = NodeType::Polygon;
+        node.num_polygon_vertices = 2;
+        node.segment_curve_polyfill_precision = 0;
+        let draw_index = stickfigure.add_node(node, DrawOrderIndex(0)).unwrap();
+
+        let diagnostics = stickfigure.apply_all_fixes().unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity == Severity::Warning && diagnostic.fix.is_none()));
+
+        let node = stickfigure.get_node(draw_index).unwrap();
+        assert_eq!(node.num_polygon_vertices, 2);
+        assert_eq!(node.segment_curve_polyfill_precision, 0);
+    }
+}
@@ -0,0 +1,559 @@
+extern crate alloc;
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{
+    match_nodes, AngleLockMode, Color, ConnectorData, ConnectorMethod, DrawOrderIndex, GradientMode, Mapping, Node, NodeType,
+    Polyfill, Stickfigure, TriangleType,
+};
+
+/// Pushes a `PropertyChange` onto `$changes` for every listed field that differs between `$old` and `$new`.
+macro_rules! compare_fields {
+    ($changes:expr, $old:expr, $new:expr, $($field:ident),+ $(,)?) => {
+        $(
+            if $old.$field != $new.$field {
+                $changes.push(PropertyChange::new(stringify!($field), &$old.$field, &$new.$field));
+            }
+        )+
+    };
+}
+
+/// Invokes `$macro` with the given arguments followed by every property of `Node` that is set by hand, i.e. every public
+/// property except its draw order index and connector data, plus the trapezoid thicknesses that aren't public.
+macro_rules! with_node_fields {
+    ($macro:ident!($($args:tt)*)) => {
+        $macro!(
+            $($args)*,
+            node_type,
+            is_static,
+            is_stretchy,
+            is_floaty,
+            is_smart_stretch,
+            do_not_apply_smart_stretch,
+            smart_stretch_reset_impulse,
+            use_segment_color,
+            use_circle_outline,
+            circle_is_hollow,
+            use_gradient,
+            reverse_gradient,
+            gradient_mode,
+            use_segment_scale,
+            scale,
+            default_length,
+            length,
+            default_thickness,
+            thickness,
+            segment_curve_radius_and_default_curve_radius,
+            curve_circulization,
+            segment_curve_polyfill_precision,
+            half_arc,
+            triangle_type,
+            triangle_flipped,
+            triangle_upside_down,
+            trapezoid_thickness_start,
+            trapezoid_thickness_end,
+            use_trapezoid_thickness_start,
+            use_trapezoid_thickness_end,
+            trapezoid_top_thickness_ratio,
+            trapezoid_is_rounded_start,
+            trapezoid_is_rounded_end,
+            num_polygon_vertices,
+            default_local_angle,
+            local_angle,
+            default_angle,
+            color,
+            gradient_color,
+            circle_outline_color,
+            angle_lock_mode,
+            angle_lock_relative_multiplier,
+            is_drag_locked,
+            drag_lock_angle,
+            smart_stretch_multiplier
+        )
+    };
+}
+pub(crate) use with_node_fields;
+
+/// A property whose value differs between two stickfigures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyChange {
+    /// Name of the field, e.g. `length`.
+    pub property: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+impl PropertyChange {
+    pub(crate) fn new<T: DiffValue>(property: &'static str, old: &T, new: &T) -> Self {
+        Self {
+            property,
+            old: old.describe(),
+            new: new.describe(),
+        }
+    }
+}
+
+/// A node that is in both stickfigures but was moved to another parent or to another place in the draw order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeMove {
+    pub old_draw_index: DrawOrderIndex,
+    pub new_draw_index: DrawOrderIndex,
+    /// Parent of the node in the old stickfigure.
+    pub old_parent: Option<DrawOrderIndex>,
+    /// Parent of the node in the new stickfigure.
+    pub new_parent: Option<DrawOrderIndex>,
+    /// Whether the node's parent is not the node its old parent became.
+    pub reparented: bool,
+    /// Whether the node is drawn in a different order relative to the other nodes in both stickfigures. Nodes whose draw
+    /// order index only shifted because nodes were added or removed before them are not reordered.
+    pub reordered: bool,
+}
+
+/// Property changes of a node that is in both stickfigures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeChange {
+    pub old_draw_index: DrawOrderIndex,
+    pub new_draw_index: DrawOrderIndex,
+    pub changes: Vec<PropertyChange>,
+}
+
+/// A difference in polyfills, which are told apart by their anchor node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolyfillChange {
+    Added { anchor_node_draw_index: DrawOrderIndex },
+    Removed { anchor_node_draw_index: DrawOrderIndex },
+    Changed {
+        old_anchor_node_draw_index: DrawOrderIndex,
+        new_anchor_node_draw_index: DrawOrderIndex,
+        changes: Vec<PropertyChange>,
+    },
+}
+
+/// A difference in the connector data of a node that is in both stickfigures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectorChange {
+    /// The node became a connector.
+    Added { draw_index: DrawOrderIndex, end_node_draw_index: DrawOrderIndex },
+    /// The node is no longer a connector.
+    Removed { draw_index: DrawOrderIndex, end_node_draw_index: DrawOrderIndex },
+    Changed {
+        old_draw_index: DrawOrderIndex,
+        new_draw_index: DrawOrderIndex,
+        changes: Vec<PropertyChange>,
+    },
+}
+
+/// Differences between two stickfigures, from `Stickfigure::diff`.
+///
+/// Draw order indices of removed nodes and `old_` fields refer to the old stickfigure. Those of added nodes and `new_`
+/// fields refer to the new one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StickfigureDiff {
+    /// Changes to the stickfigure's version, build, scale and color.
+    pub header: Vec<PropertyChange>,
+    pub added_nodes: Vec<DrawOrderIndex>,
+    pub removed_nodes: Vec<DrawOrderIndex>,
+    pub moved_nodes: Vec<NodeMove>,
+    pub node_changes: Vec<NodeChange>,
+    pub polyfill_changes: Vec<PolyfillChange>,
+    pub connector_changes: Vec<ConnectorChange>,
+}
+
+impl StickfigureDiff {
+    /// Whether the two stickfigures are the same.
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+            && self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.moved_nodes.is_empty()
+            && self.node_changes.is_empty()
+            && self.polyfill_changes.is_empty()
+            && self.connector_changes.is_empty()
+    }
+}
+
+impl Stickfigure {
+    /// Compares this stickfigure (the old one) with `other` (the new one).
+    ///
+    /// Nodes are paired with `match_nodes` rather than by draw order index, so nodes that were only renumbered are not
+    /// reported as removed and added again. Nodes of either stickfigure that aren't paired are reported as removed or added.
+    ///
+    /// # Returns
+    ///
+    /// A `StickfigureDiff`, which is empty if the two stickfigures are the same.
+    pub fn diff(&self, other: &Stickfigure) -> StickfigureDiff {
+        let mapping = match_nodes(self, other);
+        let mut diff = StickfigureDiff::default();
+
+        compare_fields!(diff.header, self, other, version, build, scale, color);
+
+        let mut old_indices = self.get_all_node_indices();
+        old_indices.sort();
+        let mut new_indices = other.get_all_node_indices();
+        new_indices.sort();
+
+        diff.removed_nodes = old_indices.iter().copied().filter(|old| mapping.target_of(*old).is_none()).collect();
+        diff.added_nodes = new_indices.iter().copied().filter(|new| mapping.source_of(*new).is_none()).collect();
+
+        let pairs: Vec<(DrawOrderIndex, DrawOrderIndex)> = old_indices
+            .iter()
+            .filter_map(|old| mapping.target_of(*old).map(|new| (*old, new)))
+            .collect();
+        let in_order = longest_increasing_run(&pairs.iter().map(|(_, new)| *new).collect::<Vec<_>>());
+
+        for (i, (old, new)) in pairs.iter().copied().enumerate() {
+            let (Some(old_node), Some(new_node)) = (self.get_node(old), other.get_node(new)) else {
+                continue;
+            };
+
+            let old_parent = self.get_parent(old);
+            let new_parent = other.get_parent(new);
+            let reparented = old_parent.and_then(|parent| mapping.target_of(parent)) != new_parent;
+            let reordered = !in_order[i];
+            if reparented || reordered {
+                diff.moved_nodes.push(NodeMove {
+                    old_draw_index: old,
+                    new_draw_index: new,
+                    old_parent,
+                    new_parent,
+                    reparented,
+                    reordered,
+                });
+            }
+
+            let changes = node_changes(old_node, new_node);
+            if !changes.is_empty() {
+                diff.node_changes.push(NodeChange {
+                    old_draw_index: old,
+                    new_draw_index: new,
+                    changes,
+                });
+            }
+
+            if let Some(change) = connector_change(old, new, old_node, new_node, &mapping) {
+                diff.connector_changes.push(change);
+            }
+        }
+
+        diff.polyfill_changes = polyfill_changes(&self.polyfills, &other.polyfills, &mapping);
+
+        diff
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for StickfigureDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No differences.");
+        }
+
+        if !self.header.is_empty() {
+            writeln!(f, "stickfigure:")?;
+            write_property_changes(f, &self.header)?;
+        }
+        for draw_index in &self.removed_nodes {
+            writeln!(f, "- node {}", draw_index.0)?;
+        }
+        for draw_index in &self.added_nodes {
+            writeln!(f, "+ node {}", draw_index.0)?;
+        }
+        for node_move in &self.moved_nodes {
+            write!(f, "~ node {} moved", node_move.old_draw_index.0)?;
+            if node_move.reordered {
+                write!(f, " to draw order index {}", node_move.new_draw_index.0)?;
+            }
+            if node_move.reparented {
+                write!(
+                    f,
+                    " from parent {} to parent {}",
+                    describe_parent(node_move.old_parent),
+                    describe_parent(node_move.new_parent)
+                )?;
+            }
+            writeln!(f)?;
+        }
+        for node_change in &self.node_changes {
+            write_node_heading(f, "node", node_change.old_draw_index, node_change.new_draw_index)?;
+            write_property_changes(f, &node_change.changes)?;
+        }
+        for change in &self.connector_changes {
+            match change {
+                ConnectorChange::Added {
+                    draw_index,
+                    end_node_draw_index,
+                } => writeln!(f, "+ connector on node {} to node {}", draw_index.0, end_node_draw_index.0)?,
+                ConnectorChange::Removed {
+                    draw_index,
+                    end_node_draw_index,
+                } => writeln!(f, "- connector on node {} to node {}", draw_index.0, end_node_draw_index.0)?,
+                ConnectorChange::Changed {
+                    old_draw_index,
+                    new_draw_index,
+                    changes,
+                } => {
+                    write_node_heading(f, "connector on node", *old_draw_index, *new_draw_index)?;
+                    write_property_changes(f, changes)?;
+                }
+            }
+        }
+        for change in &self.polyfill_changes {
+            match change {
+                PolyfillChange::Added { anchor_node_draw_index } => {
+                    writeln!(f, "+ polyfill anchored to node {}", anchor_node_draw_index.0)?
+                }
+                PolyfillChange::Removed { anchor_node_draw_index } => {
+                    writeln!(f, "- polyfill anchored to node {}", anchor_node_draw_index.0)?
+                }
+                PolyfillChange::Changed {
+                    old_anchor_node_draw_index,
+                    new_anchor_node_draw_index,
+                    changes,
+                } => {
+                    write_node_heading(f, "polyfill anchored to node", *old_anchor_node_draw_index, *new_anchor_node_draw_index)?;
+                    write_property_changes(f, changes)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+fn write_node_heading(
+    f: &mut std::fmt::Formatter<'_>,
+    what: &str,
+    old_draw_index: DrawOrderIndex,
+    new_draw_index: DrawOrderIndex,
+) -> std::fmt::Result {
+    if old_draw_index == new_draw_index {
+        writeln!(f, "{} {}:", what, new_draw_index.0)
+    } else {
+        writeln!(f, "{} {} (was {}):", what, new_draw_index.0, old_draw_index.0)
+    }
+}
+
+#[cfg(feature = "std")]
+fn write_property_changes(f: &mut std::fmt::Formatter<'_>, changes: &[PropertyChange]) -> std::fmt::Result {
+    for change in changes {
+        writeln!(f, "    {}: {} -> {}", change.property, change.old, change.new)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+fn describe_parent(parent: Option<DrawOrderIndex>) -> String {
+    parent.map_or(String::from("none"), |parent| format!("{}", parent.0))
+}
+
+/// Compares the properties of two nodes listed by `with_node_fields`.
+pub(crate) fn node_changes(old: &Node, new: &Node) -> Vec<PropertyChange> {
+    let mut changes = Vec::new();
+    with_node_fields!(compare_fields!(changes, old, new));
+    changes
+}
+
+fn connector_change(
+    old_draw_index: DrawOrderIndex,
+    new_draw_index: DrawOrderIndex,
+    old_node: &Node,
+    new_node: &Node,
+    mapping: &Mapping,
+) -> Option<ConnectorChange> {
+    match (&old_node.connector_data, &new_node.connector_data) {
+        (None, None) => None,
+        (None, Some(new)) => Some(ConnectorChange::Added {
+            draw_index: new_draw_index,
+            end_node_draw_index: new.end_node_draw_index,
+        }),
+        (Some(old), None) => Some(ConnectorChange::Removed {
+            draw_index: old_draw_index,
+            end_node_draw_index: old.end_node_draw_index,
+        }),
+        (Some(old), Some(new)) => {
+            let mut changes = connector_changes(old, new);
+            if mapping.target_of(old.end_node_draw_index) != Some(new.end_node_draw_index) {
+                changes.push(PropertyChange::new("end_node_draw_index", &old.end_node_draw_index, &new.end_node_draw_index));
+            }
+            (!changes.is_empty()).then_some(ConnectorChange::Changed {
+                old_draw_index,
+                new_draw_index,
+                changes,
+            })
+        }
+    }
+}
+
+fn connector_changes(old: &ConnectorData, new: &ConnectorData) -> Vec<PropertyChange> {
+    let mut changes = Vec::new();
+    compare_fields!(
+        changes,
+        old,
+        new,
+        local_x,
+        local_y,
+        percent,
+        percent_default,
+        value,
+        method,
+        reversed,
+        smart_stretch_ancestral_value,
+    );
+    changes
+}
+
+fn polyfill_changes(old: &[Polyfill], new: &[Polyfill], mapping: &Mapping) -> Vec<PolyfillChange> {
+    let mut changes = Vec::new();
+
+    for old_polyfill in old {
+        let new_polyfill = mapping
+            .target_of(old_polyfill.anchor_node_draw_index)
+            .and_then(|anchor| new.iter().find(|polyfill| polyfill.anchor_node_draw_index == anchor));
+        let Some(new_polyfill) = new_polyfill else {
+            changes.push(PolyfillChange::Removed {
+                anchor_node_draw_index: old_polyfill.anchor_node_draw_index,
+            });
+            continue;
+        };
+
+        let mut property_changes = Vec::new();
+        compare_fields!(property_changes, old_polyfill, new_polyfill, color, use_polyfill_color);
+        let attached_nodes_moved = old_polyfill.attached_node_draw_indices.len() != new_polyfill.attached_node_draw_indices.len()
+            || old_polyfill
+                .attached_node_draw_indices
+                .iter()
+                .zip(&new_polyfill.attached_node_draw_indices)
+                .any(|(old_attached, new_attached)| mapping.target_of(*old_attached) != Some(*new_attached));
+        if attached_nodes_moved {
+            property_changes.push(PropertyChange::new(
+                "attached_node_draw_indices",
+                &old_polyfill.attached_node_draw_indices,
+                &new_polyfill.attached_node_draw_indices,
+            ));
+        }
+
+        if !property_changes.is_empty() {
+            changes.push(PolyfillChange::Changed {
+                old_anchor_node_draw_index: old_polyfill.anchor_node_draw_index,
+                new_anchor_node_draw_index: new_polyfill.anchor_node_draw_index,
+                changes: property_changes,
+            });
+        }
+    }
+
+    for new_polyfill in new {
+        let matched = mapping
+            .source_of(new_polyfill.anchor_node_draw_index)
+            .is_some_and(|anchor| old.iter().any(|polyfill| polyfill.anchor_node_draw_index == anchor));
+        if !matched {
+            changes.push(PolyfillChange::Added {
+                anchor_node_draw_index: new_polyfill.anchor_node_draw_index,
+            });
+        }
+    }
+
+    changes
+}
+
+/// Marks which items are part of a longest strictly increasing subsequence of `values`, i.e. which stay in order.
+fn longest_increasing_run(values: &[DrawOrderIndex]) -> Vec<bool> {
+    let mut lengths = alloc::vec![1usize; values.len()];
+    let mut previous: Vec<Option<usize>> = alloc::vec![None; values.len()];
+
+    for i in 0..values.len() {
+        for j in 0..i {
+            if values[j] < values[i] && lengths[j] + 1 > lengths[i] {
+                lengths[i] = lengths[j] + 1;
+                previous[i] = Some(j);
+            }
+        }
+    }
+
+    let mut in_order = alloc::vec![false; values.len()];
+    let mut current = (0..values.len()).max_by_key(|i| lengths[*i]);
+    while let Some(i) = current {
+        in_order[i] = true;
+        current = previous[i];
+    }
+    in_order
+}
+
+/// How a compared value is shown in a `PropertyChange`.
+pub(crate) trait DiffValue {
+    fn describe(&self) -> String;
+}
+
+macro_rules! diff_value_with_display {
+    ($($t:ty),+) => {
+        $(
+            impl DiffValue for $t {
+                fn describe(&self) -> String {
+                    format!("{}", self)
+                }
+            }
+        )+
+    };
+}
+diff_value_with_display!(bool, i8, i16, i32, f32);
+
+macro_rules! diff_value_with_debug {
+    ($($t:ty),+) => {
+        $(
+            impl DiffValue for $t {
+                fn describe(&self) -> String {
+                    format!("{:?}", self)
+                }
+            }
+        )+
+    };
+}
+diff_value_with_debug!(NodeType, GradientMode, TriangleType, AngleLockMode, ConnectorMethod);
+
+impl DiffValue for Color {
+    fn describe(&self) -> String {
+        format!("rgba({}, {}, {}, {})", self.red, self.green, self.blue, self.alpha)
+    }
+}
+
+impl DiffValue for DrawOrderIndex {
+    fn describe(&self) -> String {
+        format!("{}", self.0)
+    }
+}
+
+impl DiffValue for Vec<DrawOrderIndex> {
+    fn describe(&self) -> String {
+        let indices: Vec<String> = self.iter().map(DiffValue::describe).collect();
+        format!("[{}]", indices.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NodeOptions;
+
+    #[test]
+    fn reports_changed_trapezoid_thicknesses() {
+        let mut old = Stickfigure::new();
+        let trapezoid = old
+            .add_node(
+                Node::from_options(NodeOptions {
+                    node_type: NodeType::Trapezoid,
+                    ..Default::default()
+                }),
+                DrawOrderIndex(0),
+            )
+            .unwrap();
+        let mut new = old.clone();
+        new.update_node(trapezoid, |node| {
+            node.trapezoid_thickness_end = 4.0;
+            node.trapezoid_top_thickness_ratio = 0.5;
+        })
+        .unwrap();
+
+        let diff = old.diff(&new);
+
+        let properties: Vec<&str> = diff.node_changes[0].changes.iter().map(|change| change.property).collect();
+        assert_eq!(properties, ["trapezoid_thickness_end", "trapezoid_top_thickness_ratio"]);
+    }
+}
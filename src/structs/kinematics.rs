@@ -0,0 +1,770 @@
+use glam::Vec2;
+use petgraph::Direction;
+extern crate alloc;
+use alloc::{format, string::String, vec::Vec};
+
+use crate::{AngleLockMode, DrawOrderIndex, Node, NodeType, Stickfigure, StickfigureError};
+
+/// Algorithm used by `Stickfigure::solve_ik`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IkSolver {
+    /// Forward And Backward Reaching Inverse Kinematics. Converges quickly and tends to spread the bend evenly along the chain.
+    #[default]
+    Fabrik,
+    /// Cyclic Coordinate Descent. Rotates one node at a time, starting from the end of the chain, so nodes near the end bend the most.
+    Ccd,
+}
+
+/// Tuning for `Stickfigure::solve_ik_with_options`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IkOptions {
+    /// Maximum number of solver iterations.
+    pub max_iterations: usize,
+    /// Distance from the target, in world units, at which the chain counts as having reached it.
+    pub tolerance: f32,
+    /// Whether `is_stretchy` nodes in the chain may be lengthened to reach a target that is otherwise out of reach.
+    pub allow_stretch: bool,
+}
+
+impl Default for IkOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: 32,
+            tolerance: 0.01,
+            allow_stretch: false,
+        }
+    }
+}
+
+/// How the solver may move a node of the chain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum IkHold {
+    /// The node can be rotated.
+    Free,
+    /// Static nodes can't be rotated in Stick Nodes, so they keep their local angle and turn with their parent.
+    Static,
+    /// Angle locked nodes keep their global angle, given here, whatever happens to their ancestors.
+    AngleLocked(f32),
+    /// Drag locked nodes are held at their global `drag_lock_angle`, given here, while other nodes are dragged.
+    DragLocked(f32),
+}
+
+/// A node of the chain being solved, in world units.
+#[derive(Debug, Clone, Copy)]
+struct IkJoint {
+    draw_index: DrawOrderIndex,
+    local_angle: f32,
+    length: f32,
+    scale: f32,
+    hold: IkHold,
+    is_stretchy: bool,
+}
+
+impl IkJoint {
+    /// How the solver may move `node`, whose global angle is currently `global_angle`.
+    ///
+    /// An angle lock drives the node's angle even if the node is also drag locked or static.
+    fn hold(node: &Node, global_angle: f32) -> IkHold {
+        if !matches!(node.angle_lock_mode, AngleLockMode::None) {
+            IkHold::AngleLocked(global_angle)
+        } else if node.is_drag_locked {
+            IkHold::DragLocked(node.drag_lock_angle)
+        } else if node.is_static {
+            IkHold::Static
+        } else {
+            IkHold::Free
+        }
+    }
+
+    fn is_free(&self) -> bool {
+        self.hold == IkHold::Free
+    }
+
+    /// Whether the node keeps its global angle when the nodes above it rotate.
+    fn holds_global_angle(&self) -> bool {
+        matches!(self.hold, IkHold::AngleLocked(_) | IkHold::DragLocked(_))
+    }
+}
+
+/// The chain being solved: where it starts and the nodes it is made of, from the top of the chain down.
+struct IkChain {
+    base: Vec2,
+    base_angle: f32,
+    joints: Vec<IkJoint>,
+}
+
+impl IkChain {
+    /// Computes the global angle of every node of the chain.
+    fn global_angles(&self) -> Vec<f32> {
+        let mut angle = self.base_angle;
+        self.joints
+            .iter()
+            .map(|joint| {
+                angle = match joint.hold {
+                    IkHold::AngleLocked(global_angle) | IkHold::DragLocked(global_angle) => global_angle,
+                    IkHold::Free | IkHold::Static => angle + joint.local_angle,
+                };
+                angle
+            })
+            .collect()
+    }
+
+    /// Computes the start of every node of the chain plus the end of the last node.
+    fn positions(&self) -> Vec<Vec2> {
+        let mut positions = Vec::with_capacity(self.joints.len() + 1);
+        let mut position = self.base;
+        positions.push(position);
+
+        for (joint, angle) in self.joints.iter().zip(self.global_angles()) {
+            position += direction(angle) * joint.length;
+            positions.push(position);
+        }
+
+        positions
+    }
+
+    fn end(&self) -> Vec2 {
+        self.positions().last().copied().unwrap_or(self.base)
+    }
+
+    /// Lengthens stretchy nodes just enough for the fully extended chain to reach `target`.
+    fn stretch_to_reach(&mut self, target: Vec2) {
+        let reach: f32 = self.joints.iter().map(|joint| joint.length).sum();
+        let shortfall = self.base.distance(target) - reach;
+        let stretchy_length: f32 = self
+            .joints
+            .iter()
+            .filter(|joint| joint.is_stretchy)
+            .map(|joint| joint.length)
+            .sum();
+
+        if shortfall <= 0.0 || stretchy_length <= 0.0 {
+            return;
+        }
+
+        let factor = (stretchy_length + shortfall) / stretchy_length;
+        for joint in self.joints.iter_mut().filter(|joint| joint.is_stretchy) {
+            joint.length *= factor;
+        }
+    }
+
+    fn solve_ccd(&mut self, target: Vec2, options: &IkOptions) {
+        for _ in 0..options.max_iterations {
+            if self.end().distance(target) <= options.tolerance {
+                return;
+            }
+
+            for i in (0..self.joints.len()).rev() {
+                if !self.joints[i].is_free() {
+                    continue;
+                }
+
+                let positions = self.positions();
+                let pivot = positions[i];
+                let end = positions[positions.len() - 1];
+                if pivot.distance(end) <= f32::EPSILON || pivot.distance(target) <= f32::EPSILON {
+                    continue;
+                }
+
+                let rotation = wrap_degrees(angle_of(target - pivot) - angle_of(end - pivot));
+                self.joints[i].local_angle += rotation;
+            }
+        }
+    }
+
+    fn solve_fabrik(&mut self, target: Vec2, options: &IkOptions) {
+        // Held nodes can't bend, so each rotatable node and the held nodes directly after it move as one segment.
+        // Held nodes at the top of the chain never move at all.
+        let rigid_segments: Vec<(usize, usize)> = self
+            .joints
+            .iter()
+            .enumerate()
+            .filter(|(_, joint)| joint.is_free())
+            .map(|(i, _)| {
+                let last = (i + 1..self.joints.len())
+                    .take_while(|j| !self.joints[*j].is_free())
+                    .last()
+                    .unwrap_or(i);
+                (i, last)
+            })
+            .collect();
+
+        let Some(&(first, _)) = rigid_segments.first() else {
+            return;
+        };
+
+        for _ in 0..options.max_iterations {
+            let positions = self.positions();
+            if positions[positions.len() - 1].distance(target) <= options.tolerance {
+                return;
+            }
+
+            let lengths: Vec<f32> = rigid_segments
+                .iter()
+                .map(|(first, last)| positions[*first].distance(positions[last + 1]))
+                .collect();
+            let mut points: Vec<Vec2> = core::iter::once(positions[first])
+                .chain(rigid_segments.iter().map(|(_, last)| positions[last + 1]))
+                .collect();
+
+            // Backward pass: pin the end of the chain to the target and pull every segment after it.
+            let last_point = points.len() - 1;
+            points[last_point] = target;
+            for i in (0..lengths.len()).rev() {
+                points[i] = reach_towards(points[i + 1], points[i], lengths[i]);
+            }
+
+            // Forward pass: pin the start of the chain back where it was.
+            points[0] = positions[first];
+            for i in 0..lengths.len() {
+                points[i + 1] = reach_towards(points[i], points[i + 1], lengths[i]);
+            }
+
+            // Turn the solved points back into local angles by rotating each segment's rotatable node. Only the part
+            // of the segment before its first node that holds a global angle turns with it; the rest keeps its direction.
+            for (i, (first, last)) in rigid_segments.iter().enumerate() {
+                let positions = self.positions();
+                let turning_end = (first + 1..=*last)
+                    .find(|j| self.joints[*j].holds_global_angle())
+                    .unwrap_or(last + 1);
+                let start = positions[*first];
+                let current = positions[turning_end] - start;
+                let aim = points[i + 1] - (positions[last + 1] - positions[turning_end]) - start;
+                if current.length() > f32::EPSILON && aim.length() > f32::EPSILON {
+                    self.joints[*first].local_angle += wrap_degrees(angle_of(aim) - angle_of(current));
+                }
+            }
+        }
+    }
+}
+
+impl Stickfigure {
+    /// Rotates a chain of nodes so the end of `chain_end` reaches towards `target`, using the default `IkOptions`.
+    ///
+    /// See `solve_ik_with_options`.
+    pub fn solve_ik(
+        &mut self,
+        chain_end: DrawOrderIndex,
+        chain_len: usize,
+        target: Vec2,
+        solver: IkSolver,
+    ) -> Result<f32, StickfigureError> {
+        self.solve_ik_with_options(chain_end, chain_len, target, solver, IkOptions::default())
+    }
+
+    /// Rotates a chain of nodes so the end of `chain_end` reaches towards `target`.
+    ///
+    /// Only `local_angle` is changed, plus `length` of `is_stretchy` nodes when `options.allow_stretch` is set.
+    /// Static nodes keep their local angle, angle locked nodes keep their global angle and drag locked nodes are held
+    /// at their global `drag_lock_angle`. The rest of the chain works around them.
+    /// Nodes attached below the chain move along with it, and angle locked ones among them keep their locks (see `set_angle`).
+    ///
+    /// # Parameters
+    ///
+    /// * `chain_end` - The `DrawOrderIndex` of the last node of the chain, whose end should reach the target.
+    /// * `chain_len` - How many nodes make up the chain: `chain_end` and up to `chain_len - 1` of its ancestors. The chain never includes the root node.
+    /// * `target` - Global position to reach.
+    /// * `solver` - Algorithm to use.
+    /// * `options` - Iteration limit, tolerance and whether stretching is allowed.
+    ///
+    /// # Returns
+    ///
+    /// Result of the remaining distance between the end of `chain_end` and `target`, or an error if `chain_end` does not exist.
+    pub fn solve_ik_with_options(
+        &mut self,
+        chain_end: DrawOrderIndex,
+        chain_len: usize,
+        target: Vec2,
+        solver: IkSolver,
+        options: IkOptions,
+    ) -> Result<f32, StickfigureError> {
+        if !self.draw_index_exists(chain_end) {
+            return Err(StickfigureError::InvalidDrawIndex(
+                chain_end.0,
+                String::from("Cannot solve inverse kinematics for a chain ending at a node that does not exist."),
+            ));
+        }
+
+        let mut chain = self.ik_chain(chain_end, chain_len);
+
+        if options.allow_stretch {
+            chain.stretch_to_reach(target);
+        }
+
+        match solver {
+            IkSolver::Fabrik => chain.solve_fabrik(target, &options),
+            IkSolver::Ccd => chain.solve_ccd(target, &options),
+        }
+
+        for joint in chain.joints.iter().filter(|joint| joint.is_stretchy && joint.scale > f32::EPSILON) {
+            if let Some(node) = self.get_node_mut(joint.draw_index) {
+                node.length = joint.length / joint.scale;
+            }
+        }
+
+        // Static nodes keep their local angle and angle locked ones are kept by `set_local_angles`, so only rotated and
+        // drag locked nodes are set.
+        let mut parent_global_angle = chain.base_angle;
+        let mut angles: Vec<(DrawOrderIndex, f32)> = Vec::new();
+        for (joint, global_angle) in chain.joints.iter().zip(chain.global_angles()) {
+            match joint.hold {
+                IkHold::Free => angles.push((joint.draw_index, joint.local_angle)),
+                IkHold::DragLocked(_) => angles.push((joint.draw_index, global_angle - parent_global_angle)),
+                IkHold::Static | IkHold::AngleLocked(_) => {}
+            }
+            parent_global_angle = global_angle;
+        }
+        self.set_local_angles(&angles);
+
+        Ok(chain.end().distance(target))
+    }
+
+    /// Sets the local angle of a node, then updates angle locked nodes the way Stick Nodes does when a node is rotated.
+    ///
+    /// * A node with `AngleLockMode::Absolute` keeps its global angle, whatever happens to its ancestors.
+    /// * A node with `AngleLockMode::Relative` keeps its global angle too, except that it turns along with the whole
+    ///   stickfigure: rotating the root node by some amount rotates it by that amount times `angle_lock_relative_multiplier`.
+    /// * Nodes without a lock keep their local angle, so they turn with their parent as usual.
+    ///
+    /// If the node being set is itself angle locked, its lock is reset to hold its new angle. The hidden angle lock
+    /// properties of every node are recomputed so they match what is written to `.nodes` files.
+    ///
+    /// # Parameters
+    ///
+    /// * `draw_index` - The `DrawOrderIndex` of the node to rotate.
+    /// * `local_angle` - New local angle of the node in degrees.
+    ///
+    /// # Returns
+    ///
+    /// Result of `()`, or an error if the draw order index does not exist.
+    pub fn set_angle(&mut self, draw_index: DrawOrderIndex, local_angle: f32) -> Result<(), StickfigureError> {
+        if !self.draw_index_exists(draw_index) {
+            return Err(StickfigureError::InvalidDrawIndex(
+                draw_index.0,
+                String::from("Cannot set angle."),
+            ));
+        }
+
+        self.set_local_angles(&[(draw_index, local_angle)]);
+
+        Ok(())
+    }
+
+    /// Sets the local angles of several nodes at once, then updates every other angle locked node as `set_angle` does.
+    pub(crate) fn set_local_angles(&mut self, angles: &[(DrawOrderIndex, f32)]) {
+        let before = self.compute_pose();
+        let stickfigure_angle_before = self.stickfigure_angle();
+
+        for (draw_index, local_angle) in angles {
+            if let Some(node) = self.get_node_mut(*draw_index) {
+                node.local_angle = *local_angle;
+            }
+        }
+
+        let stickfigure_angle = self.stickfigure_angle();
+        let stickfigure_rotation = stickfigure_angle - stickfigure_angle_before;
+        let was_set = |draw_index: DrawOrderIndex| angles.iter().any(|(set_index, _)| *set_index == draw_index);
+
+        let mut stack: Vec<(DrawOrderIndex, f32)> = self
+            .nodes
+            .externals(Direction::Incoming)
+            .map(|node_index| (self.draw_order_from_node_index(node_index), 0.0))
+            .collect();
+
+        while let Some((draw_index, parent_global_angle)) = stack.pop() {
+            let previous_global_angle = before.get(draw_index).map(|transform| transform.angle);
+            let Some(node) = self.get_node_mut(draw_index) else {
+                continue;
+            };
+
+            let locked_global_angle = match (&node.angle_lock_mode, previous_global_angle) {
+                _ if node.node_type == NodeType::RootNode || was_set(draw_index) => None,
+                (AngleLockMode::Absolute, Some(previous)) => Some(previous),
+                (AngleLockMode::Relative, Some(previous)) => {
+                    Some(previous + node.angle_lock_relative_multiplier as f32 * stickfigure_rotation)
+                }
+                _ => None,
+            };
+
+            if let Some(locked_global_angle) = locked_global_angle {
+                node.local_angle = locked_global_angle - parent_global_angle;
+            }
+            let global_angle = parent_global_angle + node.local_angle;
+
+            if node.node_type != NodeType::RootNode {
+                node.update_angle_lock_properties(global_angle, parent_global_angle, stickfigure_angle);
+            }
+
+            for child in self.get_children(draw_index) {
+                stack.push((child, global_angle));
+            }
+        }
+    }
+
+    /// Changes the length of a node, propagating smart stretch to its descendants.
+    ///
+    /// If the node is `is_smart_stretch`, every descendant is stretched by the same ratio as the node itself
+    /// (`new_length` over its current length), and that ratio is accumulated in the descendant's `smart_stretch_multiplier`
+    /// and, for connectors, in `smart_stretch_ancestral_value` so their ends move along with everything else.
+    /// Static descendants and those with `do_not_apply_smart_stretch` set keep their length, though nodes below them are
+    /// still stretched. A descendant with `smart_stretch_reset_impulse` set stops the stretch: it and every node below it
+    /// are left as they are. A node whose current length is 0 has no ratio to propagate, so only its own length changes.
+    ///
+    /// Any node can be stretched this way, as in the node editor. Only `is_stretchy` nodes can be stretched by dragging
+    /// in the app, which is what `solve_ik` follows.
+    ///
+    /// # Parameters
+    ///
+    /// * `draw_index` - The `DrawOrderIndex` of the node to stretch.
+    /// * `new_length` - New length of the node. Must be finite and greater than 0.
+    ///
+    /// # Returns
+    ///
+    /// Result of `()`, or an error if the draw order index does not exist or `new_length` is not a finite length
+    /// greater than 0. Nothing is changed on error.
+    pub fn stretch(&mut self, draw_index: DrawOrderIndex, new_length: f32) -> Result<(), StickfigureError> {
+        if !new_length.is_finite() || new_length <= 0.0 {
+            return Err(StickfigureError::GenericError(format!(
+                "Cannot stretch node {} to length {}: the length must be finite and greater than 0.",
+                draw_index.0, new_length
+            )));
+        }
+
+        let node = self.get_node_mut(draw_index).ok_or_else(|| {
+            StickfigureError::InvalidDrawIndex(draw_index.0, String::from("Cannot stretch node."))
+        })?;
+
+        let old_length = node.length;
+        let is_smart_stretch = node.is_smart_stretch;
+        node.length = new_length;
+
+        if !is_smart_stretch || old_length == 0.0 {
+            return Ok(());
+        }
+
+        let ratio = new_length / old_length;
+        let mut stack = self.get_children(draw_index);
+        while let Some(descendant_index) = stack.pop() {
+            let Some(descendant) = self.get_node_mut(descendant_index) else {
+                continue;
+            };
+            if descendant.smart_stretch_reset_impulse {
+                continue;
+            }
+            if !descendant.is_static && !descendant.do_not_apply_smart_stretch {
+                descendant.length *= ratio;
+                descendant.smart_stretch_multiplier *= ratio;
+                if let Some(connector_data) = descendant.connector_data.as_mut() {
+                    connector_data.smart_stretch_ancestral_value *= ratio;
+                }
+            }
+            stack.extend(self.get_children(descendant_index));
+        }
+
+        Ok(())
+    }
+
+    /// Angle of the whole stickfigure, i.e. the local angle of its root node.
+    fn stickfigure_angle(&self) -> f32 {
+        self.get_node(DrawOrderIndex(0))
+            .map(|root_node| root_node.local_angle)
+            .unwrap_or(0.0)
+    }
+
+    fn ik_chain(&self, chain_end: DrawOrderIndex, chain_len: usize) -> IkChain {
+        let pose = self.compute_pose();
+
+        let mut draw_indices: Vec<DrawOrderIndex> = core::iter::once(chain_end)
+            .chain(self.get_parents_recursive(chain_end))
+            .take_while(|draw_index| {
+                self.get_node(*draw_index)
+                    .is_some_and(|node| node.node_type != NodeType::RootNode)
+            })
+            .take(chain_len)
+            .collect();
+        draw_indices.reverse();
+
+        let joints: Vec<IkJoint> = draw_indices
+            .iter()
+            .filter_map(|draw_index| {
+                let node = self.get_node(*draw_index)?;
+                let transform = pose.get(*draw_index)?;
+                Some(IkJoint {
+                    draw_index: *draw_index,
+                    local_angle: node.local_angle,
+                    length: node.length * transform.scale,
+                    scale: transform.scale,
+                    hold: IkJoint::hold(node, transform.angle),
+                    is_stretchy: node.is_stretchy,
+                })
+            })
+            .collect();
+
+        let (base, base_angle) = match joints.first().and_then(|joint| pose.get(joint.draw_index)) {
+            Some(transform) => (transform.start, transform.angle - joints[0].local_angle),
+            None => {
+                let end = pose.get(chain_end).map(|transform| transform.end).unwrap_or(Vec2::ZERO);
+                (end, 0.0)
+            }
+        };
+
+        IkChain {
+            base,
+            base_angle,
+            joints,
+        }
+    }
+}
+
+fn direction(angle_degrees: f32) -> Vec2 {
+    let angle_radians = angle_degrees.to_radians();
+    Vec2 {
+        x: libm::cosf(angle_radians),
+        y: libm::sinf(angle_radians),
+    }
+}
+
+fn angle_of(vector: Vec2) -> f32 {
+    libm::atan2f(vector.y, vector.x).to_degrees()
+}
+
+/// Wraps an angle in degrees into -180..=180.
+fn wrap_degrees(angle_degrees: f32) -> f32 {
+    libm::remainderf(angle_degrees, 360.0)
+}
+
+/// Point `length` away from `anchor` in the direction of `towards`, or `towards` itself if the two coincide.
+fn reach_towards(anchor: Vec2, towards: Vec2, length: f32) -> Vec2 {
+    let offset = towards - anchor;
+    if offset.length() <= f32::EPSILON {
+        return towards;
+    }
+    anchor + offset.normalize() * length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+    use crate::{ConnectorData, ConnectorMethod};
+
+    const SOLVERS: [IkSolver; 2] = [IkSolver::Fabrik, IkSolver::Ccd];
+
+    /// A chain of three nodes of length 10 hanging off the root node, bent so it starts out pointing along the y axis.
+    fn arm() -> (Stickfigure, [DrawOrderIndex; 3]) {
+        let mut stickfigure = Stickfigure::new();
+        let upper = stickfigure.add_node(segment(10.0, 90.0), DrawOrderIndex(0)).unwrap();
+        let lower = stickfigure.add_node(segment(10.0, 20.0), upper).unwrap();
+        let hand = stickfigure.add_node(segment(10.0, -20.0), lower).unwrap();
+        (stickfigure, [upper, lower, hand])
+    }
+
+    fn global_angle(stickfigure: &Stickfigure, draw_index: DrawOrderIndex) -> f32 {
+        stickfigure.compute_pose().get(draw_index).unwrap().angle
+    }
+
+    fn assert_angle_eq(actual: f32, expected: f32) {
+        assert!(wrap_degrees(actual - expected).abs() < 1e-3, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn reaches_a_reachable_target() {
+        for solver in SOLVERS {
+            let (mut stickfigure, [_, _, hand]) = arm();
+            let target = Vec2::new(15.0, 10.0);
+
+            let distance = stickfigure.solve_ik(hand, 3, target, solver).unwrap();
+
+            let end = stickfigure.compute_pose().get(hand).unwrap().end;
+            assert!(distance <= IkOptions::default().tolerance, "{solver:?} stopped {distance} away");
+            assert!((end.distance(target) - distance).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn extends_towards_an_unreachable_target() {
+        for solver in SOLVERS {
+            let (mut stickfigure, [_, _, hand]) = arm();
+            let target = Vec2::new(100.0, 0.0);
+
+            let distance = stickfigure.solve_ik(hand, 3, target, solver).unwrap();
+
+            let end = stickfigure.compute_pose().get(hand).unwrap().end;
+            assert!((distance - 70.0).abs() < 0.1, "{solver:?} stopped {distance} away");
+            assert!(end.distance(Vec2::new(30.0, 0.0)) < 0.1, "{solver:?} ended at {end}");
+        }
+    }
+
+    #[test]
+    fn static_nodes_keep_their_local_angle() {
+        for solver in SOLVERS {
+            let (mut stickfigure, [upper, lower, hand]) = arm();
+            stickfigure.update_node(lower, |node| node.is_static = true).unwrap();
+
+            stickfigure.solve_ik(hand, 3, Vec2::new(15.0, 10.0), solver).unwrap();
+
+            assert_eq!(stickfigure.get_node(lower).unwrap().local_angle, 20.0);
+            assert_ne!(stickfigure.get_node(upper).unwrap().local_angle, 90.0, "{solver:?} left the chain as it was");
+        }
+    }
+
+    #[test]
+    fn angle_locked_nodes_keep_their_global_angle() {
+        for solver in SOLVERS {
+            let (mut stickfigure, [upper, lower, hand]) = arm();
+            stickfigure
+                .update_node(lower, |node| node.angle_lock_mode = AngleLockMode::Absolute)
+                .unwrap();
+            let locked_angle = global_angle(&stickfigure, lower);
+
+            let distance = stickfigure.solve_ik(hand, 3, Vec2::new(15.0, 10.0), solver).unwrap();
+
+            assert_angle_eq(global_angle(&stickfigure, lower), locked_angle);
+            assert_ne!(stickfigure.get_node(upper).unwrap().local_angle, 90.0, "{solver:?} left the chain as it was");
+            assert!(distance < 1.0, "{solver:?} stopped {distance} away");
+        }
+    }
+
+    #[test]
+    fn drag_locked_nodes_are_held_at_their_drag_lock_angle() {
+        for solver in SOLVERS {
+            let (mut stickfigure, [upper, lower, hand]) = arm();
+            stickfigure
+                .update_node(lower, |node| {
+                    node.is_drag_locked = true;
+                    node.drag_lock_angle = 45.0;
+                })
+                .unwrap();
+
+            let distance = stickfigure.solve_ik(hand, 3, Vec2::new(15.0, 10.0), solver).unwrap();
+
+            assert_angle_eq(global_angle(&stickfigure, lower), 45.0);
+            assert_ne!(stickfigure.get_node(upper).unwrap().local_angle, 90.0, "{solver:?} left the chain as it was");
+            assert!(distance < 1.0, "{solver:?} stopped {distance} away");
+        }
+    }
+
+    #[test]
+    fn stretch_skips_static_nodes_stops_at_reset_impulses_and_moves_connector_ends() {
+        let mut stickfigure = Stickfigure::new();
+        let thigh = stickfigure.add_node(segment(10.0, 0.0), DrawOrderIndex(0)).unwrap();
+        let knee = stickfigure.add_node(segment(10.0, 0.0), thigh).unwrap();
+        let shin = stickfigure.add_node(segment(10.0, 0.0), knee).unwrap();
+        let foot = stickfigure.add_node(segment(10.0, 0.0), thigh).unwrap();
+        let toe = stickfigure.add_node(segment(10.0, 0.0), foot).unwrap();
+        let arm = stickfigure.add_node(segment(100.0, 90.0), DrawOrderIndex(0)).unwrap();
+        let connector = stickfigure
+            .add_connector(
+                segment(1.0, 0.0),
+                thigh,
+                arm,
+                ConnectorData {
+                    method: ConnectorMethod::ByValue,
+                    value: 5.0,
+                    smart_stretch_ancestral_value: 1.0,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        stickfigure.update_node(thigh, |node| node.is_smart_stretch = true).unwrap();
+        stickfigure.update_node(knee, |node| node.is_static = true).unwrap();
+        stickfigure.update_node(foot, |node| node.smart_stretch_reset_impulse = true).unwrap();
+
+        stickfigure.stretch(thigh, 20.0).unwrap();
+
+        let length = |draw_index| stickfigure.get_node(draw_index).unwrap().length;
+        assert_eq!(length(knee), 10.0);
+        assert_eq!(length(shin), 20.0);
+        assert_eq!(length(foot), 10.0);
+        assert_eq!(length(toe), 10.0);
+
+        let connector_transform = *stickfigure.compute_pose().get(connector).unwrap();
+        assert!((connector_transform.offset().length() - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn stretch_refuses_lengths_that_are_not_finite_or_positive() {
+        let (mut stickfigure, [upper, lower, _]) = arm();
+        stickfigure.update_node(upper, |node| node.is_smart_stretch = true).unwrap();
+        let lengths = |stickfigure: &Stickfigure| [upper, lower].map(|index| stickfigure.get_node(index).unwrap().length);
+        let before = lengths(&stickfigure);
+
+        for new_length in [0.0, -5.0, f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(stickfigure.stretch(upper, new_length).is_err(), "stretched to {new_length}");
+            assert_eq!(lengths(&stickfigure), before);
+        }
+    }
+
+    #[test]
+    fn unlocked_nodes_keep_their_hidden_angle_lock_properties() {
+        let (mut stickfigure, [upper, lower, _]) = arm();
+        // Left over from a lock the node had before.
+        stickfigure.get_node_mut(lower).unwrap().angle_lock_offset = 12.0;
+
+        stickfigure.set_angle(upper, 45.0).unwrap();
+        assert_eq!(stickfigure.get_node(lower).unwrap().angle_lock_offset, 12.0);
+
+        let read_back = Stickfigure::from_bytes(stickfigure.to_bytes().unwrap()).unwrap();
+        let node = read_back.get_node(lower).unwrap();
+        assert_eq!(node.angle_lock_offset, 12.0);
+        assert_eq!(node.angle_lock_mode, AngleLockMode::None);
+    }
+
+    #[test]
+    fn set_angle_keeps_absolute_locks_at_their_global_angle() {
+        let (mut stickfigure, [upper, lower, hand]) = arm();
+        stickfigure
+            .update_node(lower, |node| node.angle_lock_mode = AngleLockMode::Absolute)
+            .unwrap();
+        let locked_angle = global_angle(&stickfigure, lower);
+        let hand_local_angle = stickfigure.get_node(hand).unwrap().local_angle;
+
+        stickfigure.set_angle(upper, 30.0).unwrap();
+        stickfigure.set_angle(DrawOrderIndex(0), 45.0).unwrap();
+
+        assert_angle_eq(global_angle(&stickfigure, upper), 75.0);
+        assert_angle_eq(global_angle(&stickfigure, lower), locked_angle);
+        assert_eq!(stickfigure.get_node(hand).unwrap().local_angle, hand_local_angle);
+    }
+
+    #[test]
+    fn set_angle_turns_relative_locks_with_the_root_node_only() {
+        let (mut stickfigure, [upper, lower, _]) = arm();
+        stickfigure
+            .update_node(lower, |node| {
+                node.angle_lock_mode = AngleLockMode::Relative;
+                node.angle_lock_relative_multiplier = 2;
+            })
+            .unwrap();
+        let locked_angle = global_angle(&stickfigure, lower);
+
+        stickfigure.set_angle(upper, 30.0).unwrap();
+        assert_angle_eq(global_angle(&stickfigure, lower), locked_angle);
+
+        stickfigure.set_angle(DrawOrderIndex(0), 10.0).unwrap();
+        assert_angle_eq(global_angle(&stickfigure, lower), locked_angle + 20.0);
+    }
+
+    #[test]
+    fn relative_locks_turn_the_same_however_the_stickfigure_was_loaded() {
+        let (mut stickfigure, [_, lower, _]) = arm();
+        stickfigure
+            .update_node(lower, |node| node.angle_lock_mode = AngleLockMode::Relative)
+            .unwrap();
+        stickfigure.set_angle(DrawOrderIndex(0), 10.0).unwrap();
+        let locked_angle = global_angle(&stickfigure, lower);
+
+        let loaded = [
+            Stickfigure::from_bytes(stickfigure.to_bytes().unwrap()).unwrap(),
+            Stickfigure::from_text(&stickfigure.to_text()).unwrap(),
+            Stickfigure::from_serializable(&stickfigure.to_serializable()).unwrap(),
+        ];
+        for mut loaded in loaded {
+            assert_eq!(loaded.get_node(DrawOrderIndex(0)).unwrap().local_angle, 10.0);
+
+            loaded.set_angle(DrawOrderIndex(0), 40.0).unwrap();
+
+            assert_angle_eq(global_angle(&loaded, lower), locked_angle + 30.0);
+        }
+    }
+}
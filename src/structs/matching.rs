@@ -0,0 +1,352 @@
+extern crate alloc;
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+use crate::{DrawOrderIndex, NodeType, Stickfigure};
+
+/// Lowest confidence at which `match_nodes` pairs two nodes.
+pub const MIN_MATCH_CONFIDENCE: f32 = 0.5;
+
+/// The node of the target stickfigure that a node of the source stickfigure is paired with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeMatch {
+    pub target: DrawOrderIndex,
+    /// How alike the two nodes are, from 0 to 1. Pairs made by hand have a confidence of 1.
+    pub confidence: f32,
+}
+
+/// Pairs of corresponding nodes in two stickfigures, from a node of the first (source) to a node of the second (target).
+///
+/// Every node is in at most one pair.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mapping {
+    matches: BTreeMap<DrawOrderIndex, NodeMatch>,
+    /// `matches` the other way round, from target to source.
+    sources: BTreeMap<DrawOrderIndex, DrawOrderIndex>,
+}
+
+impl Mapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pairs `source` with `target` with a confidence of 1, replacing any pair either of them was already in.
+    pub fn insert(&mut self, source: DrawOrderIndex, target: DrawOrderIndex) {
+        self.insert_with_confidence(source, target, 1.0);
+    }
+
+    /// Pairs `source` with `target`, replacing any pair either of them was already in. `confidence` is clamped to 0..=1.
+    pub fn insert_with_confidence(&mut self, source: DrawOrderIndex, target: DrawOrderIndex, confidence: f32) {
+        if let Some(previous_source) = self.sources.remove(&target) {
+            self.matches.remove(&previous_source);
+        }
+        let node_match = NodeMatch {
+            target,
+            confidence: confidence.clamp(0.0, 1.0),
+        };
+        if let Some(previous_match) = self.matches.insert(source, node_match) {
+            self.sources.remove(&previous_match.target);
+        }
+        self.sources.insert(target, source);
+    }
+
+    /// Removes the pair `source` is in, returning the target it was paired with.
+    pub fn remove(&mut self, source: DrawOrderIndex) -> Option<DrawOrderIndex> {
+        let target = self.matches.remove(&source)?.target;
+        self.sources.remove(&target);
+        Some(target)
+    }
+
+    /// Gets the target node paired with `source`.
+    pub fn target_of(&self, source: DrawOrderIndex) -> Option<DrawOrderIndex> {
+        self.matches.get(&source).map(|node_match| node_match.target)
+    }
+
+    /// Gets the source node paired with `target`.
+    pub fn source_of(&self, target: DrawOrderIndex) -> Option<DrawOrderIndex> {
+        self.sources.get(&target).copied()
+    }
+
+    /// Gets the pair `source` is in, with its confidence.
+    pub fn get(&self, source: DrawOrderIndex) -> Option<&NodeMatch> {
+        self.matches.get(&source)
+    }
+
+    /// Gets a copy of this mapping without the pairs whose confidence is below `min_confidence`.
+    pub fn with_min_confidence(&self, min_confidence: f32) -> Mapping {
+        let matches: BTreeMap<DrawOrderIndex, NodeMatch> = self
+            .matches
+            .iter()
+            .filter(|(_, node_match)| node_match.confidence >= min_confidence)
+            .map(|(source, node_match)| (*source, *node_match))
+            .collect();
+        let sources = matches.iter().map(|(source, node_match)| (node_match.target, *source)).collect();
+        Mapping { matches, sources }
+    }
+
+    /// Iterates over the pairs as `(source, target)`, in source draw order.
+    pub fn iter(&self) -> impl Iterator<Item = (DrawOrderIndex, DrawOrderIndex)> + '_ {
+        self.matches.iter().map(|(source, node_match)| (*source, node_match.target))
+    }
+
+    /// Iterates over the pairs as `(source, match)`, in source draw order.
+    pub fn matches(&self) -> impl Iterator<Item = (DrawOrderIndex, &NodeMatch)> + '_ {
+        self.matches.iter().map(|(source, node_match)| (*source, node_match))
+    }
+
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+}
+
+impl FromIterator<(DrawOrderIndex, DrawOrderIndex)> for Mapping {
+    fn from_iter<T: IntoIterator<Item = (DrawOrderIndex, DrawOrderIndex)>>(pairs: T) -> Self {
+        let mut mapping = Mapping::new();
+        for (source, target) in pairs {
+            mapping.insert(source, target);
+        }
+        mapping
+    }
+}
+
+/// What `match_nodes` compares about a node.
+struct NodeFeatures {
+    draw_index: DrawOrderIndex,
+    parent: Option<usize>,
+    node_type: NodeType,
+    depth: usize,
+    /// Number of descendants.
+    subtree_size: usize,
+    /// Position among its siblings ordered by local angle, from 0 to 1. Siblings at the same angle are ordered by draw order.
+    sibling_order: f32,
+    /// Length as a fraction of the total length of the stickfigure.
+    relative_length: f32,
+    /// Display color as red, green, blue and alpha from 0 to 1.
+    color: [f32; 4],
+}
+
+/// Pairs up corresponding nodes of two stickfigures, without relying on draw order indices.
+///
+/// Nodes are compared by:
+/// * tree structure: whether their parents are paired with each other, their depth and how many nodes hang off them;
+/// * sibling order: where they come among their siblings when those are ordered by local angle, so reordering siblings in
+///   draw order doesn't swap them;
+/// * node type;
+/// * length, relative to the total length of their stickfigure, so figures of different sizes match;
+/// * display color.
+///
+/// The root nodes are always paired. After that, the most alike pair of nodes whose source parent is already paired is
+/// paired next, until no pair left has a confidence of at least `MIN_MATCH_CONFIDENCE`. Nodes may be paired at different
+/// depths, e.g. when one figure has an extra node in a limb.
+///
+/// # Parameters
+///
+/// * `source` - First stickfigure.
+/// * `target` - Second stickfigure.
+///
+/// # Returns
+///
+/// A `Mapping` from nodes of `source` to nodes of `target`, with the confidence of every pair.
+pub fn match_nodes(source: &Stickfigure, target: &Stickfigure) -> Mapping {
+    let source_features = node_features(source);
+    let target_features = node_features(target);
+
+    // Everything but parent pairing, which depends on the pairs made so far.
+    let similarity: Vec<Vec<f32>> = source_features
+        .iter()
+        .map(|source_node| {
+            target_features
+                .iter()
+                .map(|target_node| static_similarity(source_node, target_node))
+                .collect()
+        })
+        .collect();
+
+    let mut source_to_target: Vec<Option<usize>> = vec![None; source_features.len()];
+    let mut target_is_paired = vec![false; target_features.len()];
+    let mut mapping = Mapping::new();
+
+    if let (Some(source_root), Some(target_root)) = (root(&source_features), root(&target_features)) {
+        source_to_target[source_root] = Some(target_root);
+        target_is_paired[target_root] = true;
+        mapping.insert(source_features[source_root].draw_index, target_features[target_root].draw_index);
+    }
+
+    loop {
+        let mut best: Option<(f32, usize, usize)> = None;
+
+        for (s, source_node) in source_features.iter().enumerate() {
+            let Some(paired_parent) = source_node.parent.and_then(|parent| source_to_target[parent]) else {
+                continue;
+            };
+            if source_to_target[s].is_some() {
+                continue;
+            }
+
+            for (t, target_node) in target_features.iter().enumerate() {
+                if target_is_paired[t] {
+                    continue;
+                }
+                let parent_similarity = if target_node.parent == Some(paired_parent) { 1.0 } else { 0.0 };
+                let confidence = 0.3 * parent_similarity + 0.7 * similarity[s][t];
+                if best.is_none_or(|(best_confidence, _, _)| confidence > best_confidence) {
+                    best = Some((confidence, s, t));
+                }
+            }
+        }
+
+        match best {
+            Some((confidence, s, t)) if confidence >= MIN_MATCH_CONFIDENCE => {
+                source_to_target[s] = Some(t);
+                target_is_paired[t] = true;
+                mapping.insert_with_confidence(source_features[s].draw_index, target_features[t].draw_index, confidence);
+            }
+            _ => break,
+        }
+    }
+
+    mapping
+}
+
+/// Similarity of two nodes from 0 to 1, leaving out whether their parents are paired.
+fn static_similarity(source: &NodeFeatures, target: &NodeFeatures) -> f32 {
+    let depth = 1.0 / (1.0 + source.depth.abs_diff(target.depth) as f32);
+    let subtree_size = 1.0
+        - source.subtree_size.abs_diff(target.subtree_size) as f32 / (source.subtree_size.max(target.subtree_size) + 1) as f32;
+    let sibling_order = 1.0 - (source.sibling_order - target.sibling_order).abs();
+    let node_type = if source.node_type == target.node_type { 1.0 } else { 0.0 };
+    let longest = source.relative_length.max(target.relative_length);
+    let length = if longest > 0.0 {
+        1.0 - (source.relative_length - target.relative_length).abs() / longest
+    } else {
+        1.0
+    };
+    let color_distance = source
+        .color
+        .iter()
+        .zip(target.color)
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f32>();
+    let color = 1.0 - libm::sqrtf(color_distance) / 2.0;
+
+    (0.15 * depth + 0.2 * subtree_size + 0.15 * sibling_order + 0.15 * node_type + 0.25 * length + 0.1 * color)
+        .clamp(0.0, 1.0)
+}
+
+fn root(features: &[NodeFeatures]) -> Option<usize> {
+    features.iter().position(|node| node.node_type == NodeType::RootNode)
+}
+
+fn node_features(stickfigure: &Stickfigure) -> Vec<NodeFeatures> {
+    let mut draw_indices = stickfigure.get_all_node_indices();
+    draw_indices.sort();
+
+    let total_length: f32 = draw_indices
+        .iter()
+        .filter_map(|draw_index| stickfigure.get_node(*draw_index))
+        .map(|node| node.length.abs())
+        .sum();
+    let position = |draw_index: DrawOrderIndex| draw_indices.iter().position(|existing| *existing == draw_index);
+
+    draw_indices
+        .iter()
+        .filter_map(|draw_index| {
+            let node = stickfigure.get_node(*draw_index)?;
+            let parent = stickfigure.get_parent(*draw_index);
+
+            let mut siblings: Vec<(f32, DrawOrderIndex)> = parent
+                .map(|parent| stickfigure.get_children(parent))
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|sibling| {
+                    stickfigure
+                        .get_node(sibling)
+                        .map(|sibling_node| (libm::fmodf(libm::fmodf(sibling_node.local_angle, 360.0) + 360.0, 360.0), sibling))
+                })
+                .collect();
+            siblings.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+            let rank = siblings.iter().position(|(_, sibling)| sibling == draw_index).unwrap_or(0);
+
+            let color = node.get_display_color(stickfigure);
+
+            Some(NodeFeatures {
+                draw_index: *draw_index,
+                parent: parent.and_then(position),
+                node_type: node.node_type.clone(),
+                depth: stickfigure.get_parents_recursive(*draw_index).len(),
+                subtree_size: stickfigure.get_children_recursive(*draw_index).len(),
+                sibling_order: if siblings.len() > 1 { rank as f32 / (siblings.len() - 1) as f32 } else { 0.0 },
+                relative_length: if total_length > 0.0 { node.length.abs() / total_length } else { 0.0 },
+                color: [color.red, color.green, color.blue, color.alpha].map(|channel| channel as f32 / 255.0),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+
+    /// A root node with two identical arms of two nodes each, pointing in different directions.
+    fn two_armed() -> (Stickfigure, [DrawOrderIndex; 4]) {
+        let mut stickfigure = Stickfigure::new();
+        let left_arm = stickfigure.add_node(segment(20.0, 135.0), DrawOrderIndex(0)).unwrap();
+        let left_hand = stickfigure.add_node(segment(10.0, 10.0), left_arm).unwrap();
+        let right_arm = stickfigure.add_node(segment(20.0, 45.0), DrawOrderIndex(0)).unwrap();
+        let right_hand = stickfigure.add_node(segment(10.0, 10.0), right_arm).unwrap();
+
+        (stickfigure, [left_arm, left_hand, right_arm, right_hand])
+    }
+
+    #[test]
+    fn inserting_a_pair_replaces_the_pairs_either_node_was_in() {
+        let mut mapping: Mapping = [(DrawOrderIndex(1), DrawOrderIndex(2)), (DrawOrderIndex(3), DrawOrderIndex(4))]
+            .into_iter()
+            .collect();
+
+        mapping.insert(DrawOrderIndex(1), DrawOrderIndex(4));
+
+        assert_eq!(mapping.iter().collect::<Vec<_>>(), vec![(DrawOrderIndex(1), DrawOrderIndex(4))]);
+        assert_eq!(mapping.source_of(DrawOrderIndex(4)), Some(DrawOrderIndex(1)));
+        assert_eq!(mapping.source_of(DrawOrderIndex(2)), None);
+    }
+
+    #[test]
+    fn pairs_every_node_of_an_identical_stickfigure_with_itself() {
+        let (stickfigure, nodes) = two_armed();
+
+        let mapping = match_nodes(&stickfigure, &stickfigure);
+
+        assert_eq!(mapping.len(), 5);
+        for draw_index in nodes {
+            assert_eq!(mapping.target_of(draw_index), Some(draw_index));
+        }
+    }
+
+    #[test]
+    fn reordering_siblings_in_draw_order_does_not_change_the_mapping() {
+        let (stickfigure, [left_arm, left_hand, right_arm, right_hand]) = two_armed();
+        let mut shuffled = stickfigure.clone();
+        let moved_left_arm = shuffled.bring_to_front(left_arm).unwrap();
+        let moved_left_hand = shuffled.get_children(moved_left_arm)[0];
+        let moved_right_arm = shuffled
+            .get_children(DrawOrderIndex(0))
+            .into_iter()
+            .find(|arm| *arm != moved_left_arm)
+            .unwrap();
+        let moved_right_hand = shuffled.get_children(moved_right_arm)[0];
+        assert_ne!(moved_left_arm, left_arm);
+        assert_ne!(moved_right_arm, right_arm);
+
+        let mapping = match_nodes(&stickfigure, &shuffled);
+
+        assert_eq!(mapping.target_of(left_arm), Some(moved_left_arm));
+        assert_eq!(mapping.target_of(left_hand), Some(moved_left_hand));
+        assert_eq!(mapping.target_of(right_arm), Some(moved_right_arm));
+        assert_eq!(mapping.target_of(right_hand), Some(moved_right_hand));
+    }
+}
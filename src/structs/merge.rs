@@ -0,0 +1,770 @@
+extern crate alloc;
+use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec::Vec};
+
+use crate::structs::diff::{node_changes, with_node_fields, DiffValue};
+use crate::{match_nodes, ConnectorData, DrawOrderIndex, LibraryError, Mapping, Node, Polyfill, Stickfigure};
+
+/// What a merge `Conflict` is about. Draw order indices are those of the base stickfigure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictSubject {
+    /// The stickfigure's own properties, e.g. its scale.
+    Stickfigure,
+    Node(DrawOrderIndex),
+    /// The polyfill anchored to a node.
+    Polyfill(DrawOrderIndex),
+    /// The connector data of a node.
+    Connector(DrawOrderIndex),
+}
+
+/// An edit made on both sides of a merge in different ways, or made on one side to something the other side removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub subject: ConflictSubject,
+    /// Name of the property, or `node`, `parent` or `polyfill` for conflicts about a whole node, its parent or a polyfill.
+    pub property: &'static str,
+    pub base: String,
+    pub ours: String,
+    pub theirs: String,
+}
+
+impl Conflict {
+    fn new<T: DiffValue>(subject: ConflictSubject, property: &'static str, base: &T, ours: &T, theirs: &T) -> Self {
+        Self {
+            subject,
+            property,
+            base: base.describe(),
+            ours: ours.describe(),
+            theirs: theirs.describe(),
+        }
+    }
+
+    fn described(subject: ConflictSubject, property: &'static str, base: &str, ours: &str, theirs: &str) -> Self {
+        Self {
+            subject,
+            property,
+            base: String::from(base),
+            ours: String::from(ours),
+            theirs: String::from(theirs),
+        }
+    }
+}
+
+/// The result of a `merge3` that could not combine every edit.
+#[derive(Debug, Clone)]
+pub struct Conflicts {
+    /// Every edit that could be combined, with our side of every conflict.
+    pub merged: Box<Stickfigure>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Pushes a `Conflict` onto `$conflicts`, or copies their value onto ours, for every listed field that they changed.
+macro_rules! merge_fields {
+    ($conflicts:expr, $subject:expr, $base:expr, $ours:expr, $theirs:expr, $($field:ident),+ $(,)?) => {
+        $(
+            if $theirs.$field != $base.$field {
+                if $ours.$field == $base.$field {
+                    $ours.$field = $theirs.$field.clone();
+                } else if $ours.$field != $theirs.$field {
+                    $conflicts.push(Conflict::new($subject, stringify!($field), &$base.$field, &$ours.$field, &$theirs.$field));
+                }
+            }
+        )+
+    };
+}
+
+/// Combines two sets of edits made to the same stickfigure.
+///
+/// Nodes are paired across the three stickfigures with `match_nodes`, so edits are combined even if draw order indices
+/// were shuffled on either side. Starting from `ours`, every edit in `theirs` is applied unless `ours` edited the same thing:
+/// * properties of the stickfigure, nodes, connectors and polyfills, one property at a time;
+/// * nodes added by `theirs`, which are drawn right after the node `theirs` draws before them;
+/// * nodes moved to another parent by `theirs`;
+/// * nodes and polyfills removed by `theirs`;
+/// * the version and build, as one property, which must be able to load everything else that was merged.
+///
+/// Otherwise the draw order of `ours` is kept.
+///
+/// # Parameters
+///
+/// * `base` - The stickfigure both sides started from.
+/// * `ours` - Our edited stickfigure.
+/// * `theirs` - Their edited stickfigure.
+///
+/// # Returns
+///
+/// The merged `Stickfigure`.
+///
+/// # Errors
+///
+/// Returns `Conflicts` if both sides edited the same property differently, or one side edited or added to a node or
+/// polyfill that the other removed. It holds every conflict, and the merge with our side of each of them.
+pub fn merge3(base: &Stickfigure, ours: &Stickfigure, theirs: &Stickfigure) -> Result<Stickfigure, Conflicts> {
+    let to_ours = match_nodes(base, ours);
+    let to_theirs = match_nodes(base, theirs);
+
+    let mut merge = Merge {
+        base,
+        theirs,
+        to_ours: &to_ours,
+        to_theirs: &to_theirs,
+        merged: ours.clone(),
+        added: BTreeMap::new(),
+        conflicts: Vec::new(),
+    };
+
+    merge_fields!(merge.conflicts, ConflictSubject::Stickfigure, base, merge.merged, theirs, scale, color);
+
+    merge.add_their_nodes();
+    merge.merge_nodes();
+    merge.merge_polyfills();
+    let removed = merge.their_removed_nodes();
+    let placed = merge.place_their_nodes();
+
+    // Removing a node compacts the draw order indices above it, so higher ones go first.
+    let mut removed: Vec<DrawOrderIndex> = removed.iter().filter_map(|draw_index| placed.get(draw_index).copied()).collect();
+    removed.sort();
+    for draw_index in removed.into_iter().rev() {
+        let _ = merge.merged.remove_node(draw_index);
+    }
+    merge.merge_version_and_build();
+
+    if merge.conflicts.is_empty() {
+        Ok(merge.merged)
+    } else {
+        Err(Conflicts {
+            merged: Box::new(merge.merged),
+            conflicts: merge.conflicts,
+        })
+    }
+}
+
+struct Merge<'a> {
+    base: &'a Stickfigure,
+    theirs: &'a Stickfigure,
+    to_ours: &'a Mapping,
+    to_theirs: &'a Mapping,
+    merged: Stickfigure,
+    /// Nodes added by `theirs`, from their draw order index to the one in `merged`.
+    added: BTreeMap<DrawOrderIndex, DrawOrderIndex>,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merge<'_> {
+    /// Gets the draw order index in `merged` of a node of `theirs`.
+    fn merged_from_theirs(&self, draw_index: DrawOrderIndex) -> Option<DrawOrderIndex> {
+        match self.to_theirs.source_of(draw_index) {
+            Some(base_index) => self.to_ours.target_of(base_index),
+            None => self.added.get(&draw_index).copied(),
+        }
+    }
+
+    /// Gets the draw order index in `base` of a node of `merged`, if it was there.
+    fn base_from_merged(&self, draw_index: DrawOrderIndex) -> Option<DrawOrderIndex> {
+        self.to_ours.source_of(draw_index)
+    }
+
+    /// Gets the draw order index in `base` of a node of `theirs`, if it was there.
+    fn base_from_theirs(&self, draw_index: DrawOrderIndex) -> Option<DrawOrderIndex> {
+        self.to_theirs.source_of(draw_index)
+    }
+
+    /// Adds the nodes that only `theirs` has, walking `theirs` parents first with siblings in draw order. They are drawn
+    /// after every other node until `place_their_nodes` moves them.
+    fn add_their_nodes(&mut self) {
+        let mut stack = Vec::from([DrawOrderIndex(0)]);
+        let mut connectors: Vec<(DrawOrderIndex, ConnectorData)> = Vec::new();
+
+        while let Some(their_parent) = stack.pop() {
+            let mut their_children = self.theirs.get_children(their_parent);
+            their_children.sort();
+            stack.extend(their_children.iter().rev());
+
+            for their_child in their_children {
+                if self.to_theirs.source_of(their_child).is_some() {
+                    continue;
+                }
+
+                let Some(merged_parent) = self.merged_from_theirs(their_parent) else {
+                    if let Some(base_parent) = self.base_from_theirs(their_parent) {
+                        self.conflicts.push(Conflict::described(
+                            ConflictSubject::Node(base_parent),
+                            "node",
+                            "present",
+                            "removed",
+                            "node added to it",
+                        ));
+                    }
+                    continue;
+                };
+                let Some(node) = self.theirs.get_node(their_child) else {
+                    continue;
+                };
+
+                let mut node = node.clone();
+                let connector_data = node.connector_data.take();
+                match self.merged.add_node(node, merged_parent) {
+                    Ok(merged_index) => {
+                        self.added.insert(their_child, merged_index);
+                        if let Some(connector_data) = connector_data {
+                            connectors.push((merged_index, connector_data));
+                        }
+                    }
+                    Err(err) => self.conflicts.push(Conflict::described(
+                        ConflictSubject::Node(self.base_from_theirs(their_parent).unwrap_or(DrawOrderIndex(0))),
+                        "node",
+                        "",
+                        &format!("{}", err),
+                        "node added to it",
+                    )),
+                }
+            }
+        }
+
+        // Connectors are set up once every added node exists, as they may end at a node added after them.
+        for (merged_index, mut connector_data) in connectors {
+            match self.merged_from_theirs(connector_data.end_node_draw_index) {
+                Some(end) => {
+                    connector_data.end_node_draw_index = end;
+                    if let Some(node) = self.merged.get_node_mut(merged_index) {
+                        node.connector_data = Some(connector_data);
+                    }
+                }
+                None => {
+                    let base_end = self.base_from_theirs(connector_data.end_node_draw_index).unwrap_or(DrawOrderIndex(0));
+                    self.conflicts.push(Conflict::described(
+                        ConflictSubject::Node(base_end),
+                        "node",
+                        "present",
+                        "removed",
+                        "connector added to it",
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Merges the properties, connector data and parent of every node that all three stickfigures have.
+    fn merge_nodes(&mut self) {
+        let mut base_indices = self.base.get_all_node_indices();
+        base_indices.sort();
+
+        for base_index in base_indices {
+            let (Some(our_index), Some(their_index)) = (self.to_ours.target_of(base_index), self.to_theirs.target_of(base_index))
+            else {
+                continue;
+            };
+            let (Some(base_node), Some(their_node)) = (self.base.get_node(base_index), self.theirs.get_node(their_index)) else {
+                continue;
+            };
+
+            let subject = ConflictSubject::Node(base_index);
+            if let Some(our_node) = self.merged.get_node_mut(our_index) {
+                with_node_fields!(merge_fields!(self.conflicts, subject, base_node, our_node, their_node));
+            }
+
+            self.merge_connector(base_index, our_index, base_node, their_node);
+            self.merge_parent(base_index, our_index, their_index);
+        }
+    }
+
+    fn merge_connector(&mut self, base_index: DrawOrderIndex, our_index: DrawOrderIndex, base_node: &Node, their_node: &Node) {
+        let Some(our_node) = self.merged.get_node(our_index) else {
+            return;
+        };
+
+        // Compared with end nodes in terms of the base stickfigure.
+        let base_connector = base_node.connector_data.clone();
+        let our_connector = in_base(&our_node.connector_data, |end| self.base_from_merged(end));
+        let their_connector = in_base(&their_node.connector_data, |end| self.base_from_theirs(end));
+
+        if their_connector == base_connector {
+            return;
+        }
+        if our_connector != base_connector {
+            if our_connector != their_connector {
+                self.conflicts.push(Conflict::described(
+                    ConflictSubject::Connector(base_index),
+                    "connector_data",
+                    &describe_connector(&base_connector),
+                    &describe_connector(&our_connector),
+                    &describe_connector(&their_connector),
+                ));
+            }
+            return;
+        }
+
+        let merged_connector = match their_node.connector_data.clone() {
+            None => None,
+            Some(mut connector_data) => match self.merged_from_theirs(connector_data.end_node_draw_index) {
+                Some(end) => {
+                    connector_data.end_node_draw_index = end;
+                    Some(connector_data)
+                }
+                None => {
+                    self.conflicts.push(Conflict::described(
+                        ConflictSubject::Connector(base_index),
+                        "end_node_draw_index",
+                        &describe_connector(&base_connector),
+                        "end node removed",
+                        &describe_connector(&their_connector),
+                    ));
+                    return;
+                }
+            },
+        };
+        if let Some(our_node) = self.merged.get_node_mut(our_index) {
+            our_node.connector_data = merged_connector;
+        }
+    }
+
+    fn merge_parent(&mut self, base_index: DrawOrderIndex, our_index: DrawOrderIndex, their_index: DrawOrderIndex) {
+        let base_parent = self.base.get_parent(base_index);
+        let our_parent = self.merged.get_parent(our_index);
+        let their_parent = self.theirs.get_parent(their_index);
+
+        let their_parent_in_merged = their_parent.and_then(|parent| self.merged_from_theirs(parent));
+        let their_parent_unchanged = base_parent.and_then(|parent| self.to_theirs.target_of(parent)) == their_parent;
+        let our_parent_unchanged = base_parent.and_then(|parent| self.to_ours.target_of(parent)) == our_parent;
+
+        if their_parent_unchanged || our_parent == their_parent_in_merged {
+            return;
+        }
+
+        let describe = |parent: Option<DrawOrderIndex>| parent.map_or(String::from("none"), |parent| format!("{}", parent.0));
+        let conflict = |ours: &str| {
+            Conflict::described(
+                ConflictSubject::Node(base_index),
+                "parent",
+                &describe(base_parent),
+                ours,
+                &describe(their_parent),
+            )
+        };
+
+        if !our_parent_unchanged {
+            self.conflicts.push(conflict(&describe(our_parent)));
+            return;
+        }
+        let Some(new_parent) = their_parent_in_merged else {
+            self.conflicts.push(conflict("parent removed"));
+            return;
+        };
+        if self.merged.reparent(our_index, new_parent).is_err() {
+            self.conflicts.push(conflict("parent is a descendant"));
+        }
+    }
+
+    /// Merges polyfills, which are told apart by their anchor node.
+    fn merge_polyfills(&mut self) {
+        for base_polyfill in &self.base.polyfills {
+            let base_anchor = base_polyfill.anchor_node_draw_index;
+            let subject = ConflictSubject::Polyfill(base_anchor);
+            let our_anchor = self.to_ours.target_of(base_anchor);
+            let their_polyfill = self
+                .to_theirs
+                .target_of(base_anchor)
+                .and_then(|anchor| self.theirs.get_polyfill(anchor));
+            let our_polyfill = our_anchor.and_then(|anchor| self.merged.get_polyfill(anchor));
+
+            match (our_polyfill, their_polyfill) {
+                (None, None) => {}
+                (None, Some(their_polyfill)) => {
+                    if !self.polyfill_unchanged(base_polyfill, their_polyfill, |index| self.base_from_theirs(index)) {
+                        self.conflicts.push(Conflict::described(subject, "polyfill", "present", "removed", "changed"));
+                    }
+                }
+                (Some(our_polyfill), None) => {
+                    if self.polyfill_unchanged(base_polyfill, our_polyfill, |index| self.base_from_merged(index)) {
+                        if let Some(our_anchor) = our_anchor {
+                            let _ = self.merged.remove_polyfill(our_anchor);
+                        }
+                    } else {
+                        self.conflicts.push(Conflict::described(subject, "polyfill", "present", "changed", "removed"));
+                    }
+                }
+                (Some(_), Some(their_polyfill)) => {
+                    let Some(our_anchor) = our_anchor else {
+                        continue;
+                    };
+                    self.merge_polyfill(subject, base_polyfill, our_anchor, their_polyfill);
+                }
+            }
+        }
+
+        for their_polyfill in &self.theirs.polyfills {
+            let in_base = self
+                .base_from_theirs(their_polyfill.anchor_node_draw_index)
+                .is_some_and(|anchor| self.base.get_polyfill(anchor).is_some());
+            if in_base {
+                continue;
+            }
+
+            let anchor = self.merged_from_theirs(their_polyfill.anchor_node_draw_index);
+            let attached: Option<Vec<DrawOrderIndex>> = their_polyfill
+                .attached_node_draw_indices
+                .iter()
+                .map(|index| self.merged_from_theirs(*index))
+                .collect();
+            let subject = ConflictSubject::Polyfill(
+                self.base_from_theirs(their_polyfill.anchor_node_draw_index)
+                    .unwrap_or(DrawOrderIndex(0)),
+            );
+
+            match (anchor, attached) {
+                (Some(anchor), Some(attached)) if self.merged.get_polyfill(anchor).is_none() => {
+                    self.merged.add_polyfill(Polyfill {
+                        anchor_node_draw_index: anchor,
+                        attached_node_draw_indices: attached,
+                        ..their_polyfill.clone()
+                    });
+                }
+                (Some(_), Some(_)) => {
+                    self.conflicts.push(Conflict::described(subject, "polyfill", "none", "added", "added"));
+                }
+                _ => {
+                    self.conflicts.push(Conflict::described(subject, "polyfill", "none", "node removed", "added"));
+                }
+            }
+        }
+    }
+
+    fn merge_polyfill(&mut self, subject: ConflictSubject, base_polyfill: &Polyfill, our_anchor: DrawOrderIndex, their_polyfill: &Polyfill) {
+        let base_attached = base_polyfill.attached_node_draw_indices.clone();
+        let their_attached: Vec<Option<DrawOrderIndex>> = their_polyfill
+            .attached_node_draw_indices
+            .iter()
+            .map(|index| self.base_from_theirs(*index))
+            .collect();
+        let their_attached_in_merged: Option<Vec<DrawOrderIndex>> = their_polyfill
+            .attached_node_draw_indices
+            .iter()
+            .map(|index| self.merged_from_theirs(*index))
+            .collect();
+        let base_attached_as_options: Vec<Option<DrawOrderIndex>> = base_attached.iter().copied().map(Some).collect();
+
+        let Some(our_polyfill) = self.merged.get_polyfill(our_anchor) else {
+            return;
+        };
+        let our_attached: Vec<Option<DrawOrderIndex>> = our_polyfill
+            .attached_node_draw_indices
+            .iter()
+            .map(|index| self.base_from_merged(*index))
+            .collect();
+        let our_attached_in_merged = our_polyfill.attached_node_draw_indices.clone();
+
+        let mut conflicts = Vec::new();
+        let mut merged_attached = None;
+        if their_attached != base_attached_as_options {
+            if our_attached == base_attached_as_options {
+                match their_attached_in_merged {
+                    Some(attached) => merged_attached = Some(attached),
+                    None => conflicts.push(Conflict::new(
+                        subject,
+                        "attached_node_draw_indices",
+                        &base_attached,
+                        &our_attached_in_merged,
+                        &their_polyfill.attached_node_draw_indices,
+                    )),
+                }
+            } else if our_attached != their_attached {
+                conflicts.push(Conflict::new(
+                    subject,
+                    "attached_node_draw_indices",
+                    &base_attached,
+                    &our_attached_in_merged,
+                    &their_polyfill.attached_node_draw_indices,
+                ));
+            }
+        }
+
+        if let Some(our_polyfill) = self.merged.get_polyfill_mut(our_anchor) {
+            merge_fields!(conflicts, subject, base_polyfill, our_polyfill, their_polyfill, color, use_polyfill_color);
+            if let Some(attached) = merged_attached {
+                our_polyfill.attached_node_draw_indices = attached;
+            }
+        }
+        self.conflicts.extend(conflicts);
+    }
+
+    /// Whether `polyfill` is the same as `base_polyfill`, comparing attached nodes in terms of the base stickfigure.
+    fn polyfill_unchanged(
+        &self,
+        base_polyfill: &Polyfill,
+        polyfill: &Polyfill,
+        to_base: impl Fn(DrawOrderIndex) -> Option<DrawOrderIndex>,
+    ) -> bool {
+        polyfill.color == base_polyfill.color
+            && polyfill.use_polyfill_color == base_polyfill.use_polyfill_color
+            && polyfill
+                .attached_node_draw_indices
+                .iter()
+                .map(|index| to_base(*index))
+                .eq(base_polyfill.attached_node_draw_indices.iter().copied().map(Some))
+    }
+
+    /// Moves the nodes added by `theirs` right after the node `theirs` draws before them.
+    ///
+    /// # Returns
+    ///
+    /// Where every node of `merged` went, from its old draw order index to its new one.
+    fn place_their_nodes(&mut self) -> BTreeMap<DrawOrderIndex, DrawOrderIndex> {
+        let added: Vec<DrawOrderIndex> = self.added.values().copied().collect();
+        let mut order: Vec<DrawOrderIndex> = self.merged.get_all_node_indices();
+        order.retain(|draw_index| !added.contains(draw_index));
+        order.sort();
+
+        let mut their_indices = self.theirs.get_all_node_indices();
+        their_indices.sort();
+        let mut previous = None;
+        for their_index in their_indices {
+            let Some(merged_index) = self.merged_from_theirs(their_index) else {
+                continue;
+            };
+            if self.added.contains_key(&their_index) {
+                let position = previous
+                    .and_then(|previous| order.iter().position(|draw_index| *draw_index == previous))
+                    .map_or(0, |position| position + 1);
+                order.insert(position, merged_index);
+            }
+            previous = Some(merged_index);
+        }
+
+        self.merged.set_draw_order(&order);
+        order
+            .into_iter()
+            .enumerate()
+            .map(|(new_index, old_index)| (old_index, DrawOrderIndex(new_index as i32)))
+            .collect()
+    }
+
+    /// Merges the version and build through `set_version_and_build`, reporting a conflict if the merged stickfigure uses
+    /// anything they can't load.
+    fn merge_version_and_build(&mut self) {
+        let base = (self.base.version, self.base.build);
+        let ours = (self.merged.version, self.merged.build);
+        let theirs = (self.theirs.version, self.theirs.build);
+        let describe = |(version, build): (i32, i32)| format!("{version} build {build}");
+        let conflict = |ours: &str| {
+            Conflict::described(ConflictSubject::Stickfigure, "version", &describe(base), ours, &describe(theirs))
+        };
+
+        if theirs != base && ours != base && ours != theirs {
+            self.conflicts.push(conflict(&describe(ours)));
+            return;
+        }
+
+        let (version, build) = if theirs != base { theirs } else { ours };
+        let result = if (version, build) == ours {
+            self.merged.check_capabilities().map_err(LibraryError::from)
+        } else {
+            self.merged.set_version_and_build(version, build)
+        };
+        if let Err(error) = result {
+            self.conflicts.push(conflict(&format!("{} can't load the merge: {error}", describe((version, build)))));
+        }
+    }
+
+    /// Gets the nodes that `theirs` removed and we didn't change, and reports the ones we removed but they changed.
+    fn their_removed_nodes(&mut self) -> Vec<DrawOrderIndex> {
+        let mut to_remove = Vec::new();
+        let mut base_indices = self.base.get_all_node_indices();
+        base_indices.sort();
+
+        for base_index in base_indices {
+            let Some(base_node) = self.base.get_node(base_index) else {
+                continue;
+            };
+            let subject = ConflictSubject::Node(base_index);
+
+            match (self.to_ours.target_of(base_index), self.to_theirs.target_of(base_index)) {
+                (Some(our_index), None) => {
+                    let ours_unchanged = self.merged.get_node(our_index).is_some_and(|our_node| {
+                        node_changes(base_node, our_node).is_empty()
+                            && in_base(&our_node.connector_data, |end| self.base_from_merged(end)) == base_node.connector_data
+                    });
+                    if ours_unchanged {
+                        to_remove.push(our_index);
+                    } else {
+                        self.conflicts.push(Conflict::described(subject, "node", "present", "changed", "removed"));
+                    }
+                }
+                (None, Some(their_index)) => {
+                    let theirs_changed = self.theirs.get_node(their_index).is_some_and(|their_node| {
+                        !node_changes(base_node, their_node).is_empty()
+                            || in_base(&their_node.connector_data, |end| self.base_from_theirs(end)) != base_node.connector_data
+                    });
+                    if theirs_changed {
+                        self.conflicts.push(Conflict::described(subject, "node", "present", "removed", "changed"));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        to_remove
+    }
+}
+
+/// Gets `connector_data` with its end node given in terms of the base stickfigure, or `DrawOrderIndex(-1)` if it isn't in it.
+fn in_base(
+    connector_data: &Option<ConnectorData>,
+    to_base: impl Fn(DrawOrderIndex) -> Option<DrawOrderIndex>,
+) -> Option<ConnectorData> {
+    connector_data.clone().map(|mut connector_data| {
+        connector_data.end_node_draw_index = to_base(connector_data.end_node_draw_index).unwrap_or(DrawOrderIndex(-1));
+        connector_data
+    })
+}
+
+fn describe_connector(connector_data: &Option<ConnectorData>) -> String {
+    match connector_data {
+        None => String::from("none"),
+        Some(connector_data) => format!("{:?}", connector_data),
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.subject {
+            ConflictSubject::Stickfigure => write!(f, "stickfigure")?,
+            ConflictSubject::Node(draw_index) => write!(f, "node {}", draw_index.0)?,
+            ConflictSubject::Polyfill(draw_index) => write!(f, "polyfill anchored to node {}", draw_index.0)?,
+            ConflictSubject::Connector(draw_index) => write!(f, "connector on node {}", draw_index.0)?,
+        }
+        write!(f, " {}: base {}, ours {}, theirs {}", self.property, self.base, self.ours, self.theirs)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for Conflicts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} merge conflict(s):", self.conflicts.len())?;
+        for conflict in &self.conflicts {
+            writeln!(f, "    {}", conflict)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Conflicts {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+    use crate::{Color, NodeType};
+
+    /// A root node with an arm (draw index 1) and a leg (draw index 2).
+    fn base() -> Stickfigure {
+        let mut stickfigure = Stickfigure::new();
+        stickfigure.add_node(segment(50.0, 0.0), DrawOrderIndex(0)).unwrap();
+        stickfigure.add_node(segment(80.0, 90.0), DrawOrderIndex(0)).unwrap();
+        stickfigure
+    }
+
+    fn length(stickfigure: &Stickfigure, draw_index: i32) -> f32 {
+        stickfigure.get_node(DrawOrderIndex(draw_index)).unwrap().length
+    }
+
+    #[test]
+    fn keeps_edits_made_only_by_us() {
+        let base = base();
+        let mut ours = base.clone();
+        ours.update_node(DrawOrderIndex(1), |node| node.length = 60.0).unwrap();
+
+        let merged = merge3(&base, &ours, &base).unwrap();
+
+        assert_eq!(length(&merged, 1), 60.0);
+        assert!(ours.diff(&merged).is_empty());
+    }
+
+    #[test]
+    fn applies_edits_made_only_by_them() {
+        let base = base();
+        let mut theirs = base.clone();
+        theirs
+            .update_node(DrawOrderIndex(2), |node| {
+                node.color = Color::from_rgba(255, 0, 0, 255);
+                node.node_type = NodeType::Trapezoid;
+                node.trapezoid_thickness_end = 4.0;
+            })
+            .unwrap();
+
+        let merged = merge3(&base, &base, &theirs).unwrap();
+
+        assert!(theirs.diff(&merged).is_empty());
+    }
+
+    #[test]
+    fn reports_conflicting_edits_and_keeps_ours() {
+        let base = base();
+        let mut ours = base.clone();
+        ours.update_node(DrawOrderIndex(1), |node| node.length = 60.0).unwrap();
+        let mut theirs = base.clone();
+        theirs.update_node(DrawOrderIndex(1), |node| node.length = 40.0).unwrap();
+
+        let conflicts = merge3(&base, &ours, &theirs).unwrap_err();
+
+        assert_eq!(conflicts.conflicts.len(), 1);
+        assert_eq!(conflicts.conflicts[0].subject, ConflictSubject::Node(DrawOrderIndex(1)));
+        assert_eq!(conflicts.conflicts[0].property, "length");
+        assert_eq!(length(&conflicts.merged, 1), 60.0);
+    }
+
+    #[test]
+    fn moves_nodes_they_gave_another_parent() {
+        let base = base();
+        let mut theirs = base.clone();
+        theirs.reparent(DrawOrderIndex(2), DrawOrderIndex(1)).unwrap();
+
+        let merged = merge3(&base, &base, &theirs).unwrap();
+
+        assert_eq!(merged.get_parent(DrawOrderIndex(2)), Some(DrawOrderIndex(1)));
+    }
+
+    #[test]
+    fn merges_the_version_through_set_version_and_build() {
+        let base = base();
+        let mut ours = base.clone();
+        ours.set_version_and_build(403, 40).unwrap();
+        let mut theirs = base.clone();
+        theirs.update_node(DrawOrderIndex(1), |node| node.is_floaty = true).unwrap();
+
+        let conflicts = merge3(&base, &ours, &theirs).unwrap_err();
+
+        assert_eq!(conflicts.conflicts.len(), 1);
+        assert_eq!(conflicts.conflicts[0].subject, ConflictSubject::Stickfigure);
+        assert_eq!(conflicts.conflicts[0].property, "version");
+
+        let mut theirs = base.clone();
+        theirs.set_version_and_build(403, 40).unwrap();
+
+        let merged = merge3(&base, &base, &theirs).unwrap();
+
+        assert_eq!((merged.version, merged.build), (403, 40));
+    }
+
+    #[test]
+    fn draws_added_siblings_where_they_are_drawn_in_theirs() {
+        let base = base();
+        let mut ours = base.clone();
+        ours.add_node(segment(10.0, 30.0), DrawOrderIndex(1)).unwrap();
+        let mut theirs = base.clone();
+        theirs
+            .add_node_at_index(segment(20.0, 180.0), DrawOrderIndex(0), DrawOrderIndex(2))
+            .unwrap();
+        theirs
+            .add_node_at_index(segment(30.0, 270.0), DrawOrderIndex(0), DrawOrderIndex(3))
+            .unwrap();
+
+        let merged = merge3(&base, &ours, &theirs).unwrap();
+
+        // Their two new siblings are drawn between the arm and the leg, and our new node after everything we had.
+        let lengths: Vec<f32> = (1..=5).map(|draw_index| length(&merged, draw_index)).collect();
+        assert_eq!(lengths, [50.0, 20.0, 30.0, 80.0, 10.0]);
+        assert_eq!(merged.get_parent(DrawOrderIndex(2)), Some(DrawOrderIndex(0)));
+        assert_eq!(merged.get_parent(DrawOrderIndex(5)), Some(DrawOrderIndex(1)));
+    }
+}
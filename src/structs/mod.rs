@@ -2,3 +2,15 @@ pub(crate) mod node;
 pub(crate) mod polyfill;
 pub(crate) mod stickfigure;
 pub(crate) mod connector;
+pub(crate) mod diagnostic;
+pub(crate) mod capabilities;
+pub(crate) mod pose;
+pub(crate) mod kinematics;
+pub(crate) mod shape;
+pub(crate) mod bounds;
+pub(crate) mod pose_snapshot;
+pub(crate) mod animation;
+pub(crate) mod matching;
+pub(crate) mod retarget;
+pub(crate) mod diff;
+pub(crate) mod merge;
@@ -8,11 +8,11 @@ use crate::color::Color;
 use crate::Stickfigure;
 
 use super::connector::ConnectorData;
+use super::pose::NodeTransform;
 use super::stickfigure::DrawOrderIndex;
 
-use core::cell::RefCell;
 extern crate alloc;
-use alloc::{rc::Rc, vec::Vec};
+use alloc::vec::Vec;
 
 #[derive(Debug, Clone)]
 pub struct Node {
@@ -145,7 +145,12 @@ impl Default for Node {
     }
 }
 
+fn no_trapezoid_taper() -> f32 {
+    -1.0
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SerializableNode {
     pub node_type: NodeType,
     pub draw_order_index: DrawOrderIndex,
@@ -178,6 +183,9 @@ pub struct SerializableNode {
     pub trapezoid_thickness_end: f32,
     pub use_trapezoid_thickness_start: bool,
     pub use_trapezoid_thickness_end: bool,
+    /// Taper of trapezoids saved before build 36, which store no start and end thickness. Missing means no taper (-1).
+    #[serde(default = "no_trapezoid_taper")]
+    pub trapezoid_top_thickness_ratio: f32,
     pub trapezoid_is_rounded_start: bool,
     pub trapezoid_is_rounded_end: bool,
     pub num_polygon_vertices: i16,
@@ -196,7 +204,10 @@ pub struct SerializableNode {
     pub children: Vec<SerializableNode>,
 }
 
+/// Options to create a node from with `Node::from_options`. Fields left out when deserializing take their value from
+/// `NodeOptions::default()`.
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NodeOptions {
     pub node_type: NodeType,
     pub is_static: bool,
@@ -341,6 +352,7 @@ impl Node {
             is_floaty: options.is_floaty,
             smart_stretch_reset_impulse: options.smart_stretch_reset_impulse,
             triangle_type: options.triangle_type,
+            triangle_flipped: options.triangle_flipped,
             trapezoid_thickness_start: options.trapezoid_thickness_start,
             trapezoid_thickness_end: options.trapezoid_thickness_end,
             use_trapezoid_thickness_start: options.use_trapezoid_thickness_start,
@@ -410,10 +422,10 @@ impl Node {
 
     pub fn build_serializable_tree(
         &self,
-        graph: &Graph<Rc<RefCell<Node>>, ()>,
+        graph: &Graph<Node, ()>,
         current: NodeIndex,
     ) -> SerializableNode {
-        let node = graph.node_weight(current).expect("Bug with library. NodeIndex passed to build_serializable_tree in Node is not valid.").borrow().clone();
+        let node = graph.node_weight(current).expect("Bug with library. NodeIndex passed to build_serializable_tree in Node is not valid.").clone();
         let mut children = Vec::new();
 
         for neighbor in graph.neighbors_directed(current, petgraph::Direction::Outgoing) {
@@ -460,6 +472,7 @@ impl Node {
             trapezoid_thickness_end: node.trapezoid_thickness_end,
             use_trapezoid_thickness_start: node.use_trapezoid_thickness_start,
             use_trapezoid_thickness_end: node.use_trapezoid_thickness_end,
+            trapezoid_top_thickness_ratio: node.trapezoid_top_thickness_ratio,
             trapezoid_is_rounded_start: node.trapezoid_is_rounded_start,
             trapezoid_is_rounded_end: node.trapezoid_is_rounded_end,
             angle_lock_mode: node.angle_lock_mode,
@@ -471,36 +484,66 @@ impl Node {
         }
     }
 
-    pub fn get_draw_order_index(&self) -> DrawOrderIndex {
-        return self.draw_order_index;
+    /// Creates a `Node` from the properties of a `SerializableNode`, leaving out its children.
+    pub fn from_serializable(node: &SerializableNode) -> Self {
+        Self {
+            node_type: node.node_type.clone(),
+            draw_order_index: node.draw_order_index,
+            is_static: node.is_static,
+            is_stretchy: node.is_stretchy,
+            is_floaty: node.is_floaty,
+            is_smart_stretch: node.is_smart_stretch,
+            do_not_apply_smart_stretch: node.do_not_apply_smart_stretch,
+            smart_stretch_reset_impulse: node.smart_stretch_reset_impulse,
+            use_segment_color: node.use_segment_color,
+            use_circle_outline: node.use_circle_outline,
+            circle_is_hollow: node.circle_is_hollow,
+            use_gradient: node.use_gradient,
+            reverse_gradient: node.reverse_gradient,
+            gradient_mode: node.gradient_mode.clone(),
+            use_segment_scale: node.use_segment_scale,
+            scale: node.scale,
+            default_length: node.default_length,
+            length: node.length,
+            default_thickness: node.default_thickness,
+            thickness: node.thickness,
+            segment_curve_radius_and_default_curve_radius: node.segment_curve_radius_and_default_curve_radius,
+            curve_circulization: node.curve_circulization,
+            segment_curve_polyfill_precision: node.segment_curve_polyfill_precision,
+            half_arc: node.half_arc,
+            triangle_type: node.triangle_type.clone(),
+            triangle_flipped: node.triangle_flipped,
+            triangle_upside_down: node.triangle_upside_down,
+            trapezoid_thickness_start: node.trapezoid_thickness_start,
+            trapezoid_thickness_end: node.trapezoid_thickness_end,
+            use_trapezoid_thickness_start: node.use_trapezoid_thickness_start,
+            use_trapezoid_thickness_end: node.use_trapezoid_thickness_end,
+            trapezoid_top_thickness_ratio: node.trapezoid_top_thickness_ratio,
+            trapezoid_is_rounded_start: node.trapezoid_is_rounded_start,
+            trapezoid_is_rounded_end: node.trapezoid_is_rounded_end,
+            num_polygon_vertices: node.num_polygon_vertices,
+            default_local_angle: node.default_local_angle,
+            local_angle: node.local_angle,
+            default_angle: node.default_angle,
+            color: node.color,
+            gradient_color: node.gradient_color,
+            circle_outline_color: node.circle_outline_color,
+            angle_lock_mode: node.angle_lock_mode.clone(),
+            angle_lock_relative_multiplier: node.angle_lock_relative_multiplier,
+            is_drag_locked: node.is_drag_locked,
+            drag_lock_angle: node.drag_lock_angle,
+            smart_stretch_multiplier: node.smart_stretch_multiplier,
+            connector_data: node.connector_data.clone(),
+            ..Node::default()
+        }
     }
 
-    pub fn update<F>(rc_node: &Rc<RefCell<Self>>, f: F)
-    where
-        F: FnOnce(&mut Self),
-    {
-        let mut inner = rc_node.borrow_mut();
-        f(&mut *inner);
+    pub fn get_draw_order_index(&self) -> DrawOrderIndex {
+        return self.draw_order_index;
     }
 
     pub fn get_global_angle(&self, stickfigure: &Stickfigure) -> f32 {
-        let ancestors = stickfigure.get_parents_recursive(self.get_draw_order_index());
-        let mut global_angle = 0.0;
-
-        ancestors.iter().for_each(|ancestor| {
-            if let Some(node) = stickfigure.get_node(*ancestor) {
-                let angle = node.borrow().local_angle;
-
-                global_angle += angle;
-            }
-            
-        });
-
-        let angle = self.local_angle;
-
-        global_angle += angle;
-
-        global_angle
+        self.get_global_transform(stickfigure).angle
     }
 
     pub fn get_local_x(&self, stickfigure: &Stickfigure) -> f32 {
@@ -517,33 +560,20 @@ impl Node {
     }
 
     pub fn get_global_start(&self, stickfigure: &Stickfigure) -> Vec2 {
-        let ancestors = stickfigure.get_parents_recursive(self.get_draw_order_index());
-        let mut global_position = Vec2 {x: 0.0, y: 0.0 };
-        
-
-        ancestors.iter().for_each(|ancestor| {
-            if let Some(node) = stickfigure.get_node(*ancestor) {
-                let mut scale_factor = 1.0;
-                if node.borrow().use_segment_scale {
-                    scale_factor = self.scale;
-                }
-
-                global_position += node.borrow().get_local_position(stickfigure) * scale_factor;
-            }
-        });
-
-        global_position
+        stickfigure.compute_parent_transform(self.get_draw_order_index()).end
     }
 
     pub fn get_global_end(&self, stickfigure: &Stickfigure) -> Vec2 {
-        let mut scale_factor = 1.0;
-        if self.use_segment_scale {
-            scale_factor = self.scale;
-        }
-
-        let global_start = self.get_global_start(stickfigure);
+        self.get_global_transform(stickfigure).end
+    }
 
-        global_start + (self.get_local_position(stickfigure) * scale_factor)
+    /// Gets the world start, end, angle and effective scale of this node.
+    ///
+    /// This computes the transforms of the node's ancestors, and of the nodes that connectors among them connect to, on
+    /// every call. To get the transforms of many nodes, compute a `Pose` once with `Stickfigure::compute_pose` and read
+    /// them from it instead.
+    pub fn get_global_transform(&self, stickfigure: &Stickfigure) -> NodeTransform {
+        stickfigure.compute_transform(self.get_draw_order_index())
     }
 
     pub fn get_display_color(&self, stickfigure: &Stickfigure) -> Color {
@@ -551,6 +581,8 @@ impl Node {
         else { return self.color }
     }
 
+    /// Gets the thickness of this node times its own segment scale.
+    #[deprecated(note = "ignores inherited segment scale and the stickfigure's scale; use `get_drawn_thickness`")]
     pub fn get_thickness(&self) -> i32 {
         let mut scale_factor = 1.0;
 
@@ -561,18 +593,78 @@ impl Node {
         ((self.thickness as f32) * scale_factor) as i32
     }
 
+    /// Gets the drawn thickness of this node, including inherited segment scale and the stickfigure's scale.
+    pub fn get_drawn_thickness(&self, stickfigure: &Stickfigure) -> i32 {
+        self.get_global_transform(stickfigure).thickness as i32
+    }
+
+    /// Gets the drawn thickness of the start of this trapezoid node, scaled like `get_drawn_thickness`.
     pub fn get_trapezoid_thickness_start(&self, stickfigure: &Stickfigure) -> f32 {
-        if stickfigure.version < 403 || stickfigure.build < 36 { return self.thickness as f32 }
+        self.trapezoid_thickness_start_for(stickfigure.version, stickfigure.build) * self.get_global_transform(stickfigure).scale
+    }
+
+    /// Gets the drawn thickness of the end of this trapezoid node, scaled like `get_drawn_thickness`.
+    pub fn get_trapezoid_thickness_end(&self, stickfigure: &Stickfigure) -> f32 {
+        self.trapezoid_thickness_end_for(stickfigure.version, stickfigure.build) * self.get_global_transform(stickfigure).scale
+    }
+
+    pub(crate) fn trapezoid_thickness_start_for(&self, version: i32, build: i32) -> f32 {
+        if version < 403 || build < 36 { return self.thickness as f32 }
         if !self.use_trapezoid_thickness_start { return self.thickness as f32 }
         self.trapezoid_thickness_start
     }
 
-    pub fn get_trapezoid_thickness_end(&self, stickfigure: &Stickfigure) -> f32 {
-        if stickfigure.version < 403 || stickfigure.build < 36 { return (self.thickness as f32) * self.trapezoid_top_thickness_ratio }
+    pub(crate) fn trapezoid_thickness_end_for(&self, version: i32, build: i32) -> f32 {
+        if version < 403 || build < 36 { return (self.thickness as f32) * self.trapezoid_top_thickness_ratio }
         if !self.use_trapezoid_thickness_end { return self.thickness as f32 }
         self.trapezoid_thickness_end
     }
 
+    /// The `right_triangle_direction` that `.nodes` files store for this node's `triangle_type` and `triangle_flipped`:
+    /// 0 for an isosceles triangle, and 1 or -1 for the side of a right triangle's right angle.
+    pub(crate) fn resolved_right_triangle_direction(&self) -> i16 {
+        match self.triangle_type {
+            TriangleType::Isosceles => 0,
+            TriangleType::RightTriangle if self.triangle_flipped => -1,
+            TriangleType::RightTriangle => 1,
+        }
+    }
+
+    /// Recomputes the hidden angle lock properties from `angle_lock_mode` and the node's current angles,
+    /// the same way Stick Nodes records them when a lock is set.
+    ///
+    /// Nodes without a lock are only marked as unlocked, and keep the rest of the hidden properties they were read with.
+    ///
+    /// # Parameters
+    ///
+    /// * `global_angle` - Global angle of this node.
+    /// * `parent_global_angle` - Global angle of this node's parent.
+    /// * `stickfigure_angle` - Angle of the whole stickfigure, i.e. the local angle of its root node.
+    pub(crate) fn update_angle_lock_properties(&mut self, global_angle: f32, parent_global_angle: f32, stickfigure_angle: f32) {
+        match self.angle_lock_mode {
+            AngleLockMode::None => {
+                self.is_angle_locked = false;
+                return;
+            },
+            AngleLockMode::Absolute => {
+                self.is_angle_locked = true;
+                self.angle_lock_is_main_node = true;
+                self.angle_lock_relative_start = 0.0;
+                self.angle_lock_stickfigure_start = 0.0;
+            },
+            AngleLockMode::Relative => {
+                self.is_angle_locked = true;
+                self.angle_lock_is_main_node = false;
+                self.angle_lock_relative_start = parent_global_angle;
+                self.angle_lock_stickfigure_start = stickfigure_angle;
+            },
+        }
+
+        self.angle_lock_offset_minuend = global_angle;
+        self.angle_lock_offset_subtrahend = parent_global_angle;
+        self.angle_lock_offset = self.angle_lock_offset_minuend - self.angle_lock_offset_subtrahend;
+    }
+
     fn length_angle_to_xy(&self, stickfigure: &Stickfigure) -> Vec2 {
         let length = self.length;
         let angle_degrees = self.get_global_angle(stickfigure);
@@ -585,6 +677,7 @@ impl Node {
 
 #[repr(i8)]
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum NodeType {
     /// Should only be one RootNode per `Stickfigure`, and it should be the first node.
     RootNode = -1,
@@ -620,7 +713,8 @@ impl NodeType {
 }
 
 #[repr(u8)]
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum GradientMode {
     Sideways = 0,
     #[default]
@@ -641,7 +735,8 @@ impl GradientMode {
 }
 
 #[repr(u8)]
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum AngleLockMode {
     #[default]
     None = 0,
@@ -664,7 +759,8 @@ impl AngleLockMode {
 }
 
 #[repr(u8)]
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum TriangleType {
     #[default]
     Isosceles = 0,
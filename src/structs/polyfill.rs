@@ -5,11 +5,13 @@ use serde::Serialize;
 extern crate alloc;
 use alloc::{format, vec::Vec};
 
-use crate::{color::Color, Stickfigure, StickfigureError};
+use crate::{color::Color, Pose, Stickfigure, StickfigureError};
 
+use super::shape::{triangulate, FillRule};
 use super::stickfigure::DrawOrderIndex;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Polyfill {
     pub anchor_node_draw_index: DrawOrderIndex,
     pub color: Color,
@@ -17,6 +19,14 @@ pub struct Polyfill {
     pub attached_node_draw_indices: Vec<DrawOrderIndex>,
 }
 
+/// Triangulated area of a polyfill, in global coordinates.
+#[derive(Debug, Clone)]
+pub struct PolyfillMesh {
+    pub triangles: Vec<[Vec2; 3]>,
+    /// Color the polyfill is drawn in (see `Polyfill::get_display_color`).
+    pub color: Color,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PolyfillOptions {
     pub anchor_node_draw_index: DrawOrderIndex,
@@ -50,7 +60,7 @@ impl Default for PolyfillOptions {
 impl Polyfill {
     pub fn from_options(
         options: PolyfillOptions,
-        stickfigure: Stickfigure,
+        stickfigure: &Stickfigure,
     ) -> Result<Self, StickfigureError> {
         let mut indices_to_check = options.attached_node_draw_indices.clone();
         indices_to_check.insert(0, options.anchor_node_draw_index);
@@ -90,7 +100,7 @@ impl Polyfill {
     pub fn set_attached_node_draw_indices(
         &mut self,
         draw_indices: Vec<DrawOrderIndex>,
-        stickfigure: Stickfigure,
+        stickfigure: &Stickfigure,
     ) -> Result<(), StickfigureError> {
         let missing_indices = stickfigure.missing_draw_indices(&draw_indices);
         if missing_indices.iter().count() > 0 {
@@ -114,7 +124,7 @@ impl Polyfill {
     pub fn set_anchor_node_draw_index(
         &mut self,
         draw_index: DrawOrderIndex,
-        stickfigure: Stickfigure,
+        stickfigure: &Stickfigure,
     ) -> Result<(), StickfigureError> {
         if !stickfigure.draw_index_exists(draw_index) {
             return Err(StickfigureError::InvalidDrawIndex(
@@ -137,7 +147,7 @@ impl Polyfill {
         &mut self,
         draw_indices: Vec<DrawOrderIndex>,
         insert_after_draw_index: DrawOrderIndex,
-        stickfigure: Stickfigure,
+        stickfigure: &Stickfigure,
     ) -> Result<(), StickfigureError> {
         let missing_indices = stickfigure.missing_draw_indices(&draw_indices);
         if missing_indices.iter().count() > 0 {
@@ -168,7 +178,7 @@ impl Polyfill {
         &mut self,
         draw_indices: Vec<DrawOrderIndex>,
         insert_before_draw_index: DrawOrderIndex,
-        stickfigure: Stickfigure,
+        stickfigure: &Stickfigure,
     ) -> Result<(), StickfigureError> {
         let missing_indices = stickfigure.missing_draw_indices(&draw_indices);
         if missing_indices.iter().count() > 0 {
@@ -198,7 +208,7 @@ impl Polyfill {
     pub fn remove_attached_node_draw_indices(
         &mut self,
         draw_indices: Vec<DrawOrderIndex>,
-        stickfigure: Stickfigure,
+        stickfigure: &Stickfigure,
     ) -> Result<(), StickfigureError> {
         let missing_indices = stickfigure.missing_draw_indices(&draw_indices);
         if missing_indices.iter().count() > 0 {
@@ -223,7 +233,7 @@ impl Polyfill {
     pub fn try_set_attached_node_draw_indices(
         &mut self,
         draw_indices: Vec<DrawOrderIndex>,
-        stickfigure: Stickfigure,
+        stickfigure: &Stickfigure,
     ) -> Vec<DrawOrderIndex> {
         let missing_indices = stickfigure.missing_draw_indices(&draw_indices);
 
@@ -239,17 +249,84 @@ impl Polyfill {
     }
 
     pub fn get_global_vertices(&self, stickfigure: &Stickfigure) -> Vec<Vec2> {
-        let anchor_node = stickfigure.get_node(self.anchor_node_draw_index).expect("this is an internal library error because the anchor node draw index should always be valid");
+        let pose = stickfigure.compute_pose();
+        let global_end = |draw_index: &DrawOrderIndex| {
+            pose.get(*draw_index).expect("this is an internal library error because all polyfill draw indices should always be valid").end
+        };
+
+        core::iter::once(&self.anchor_node_draw_index)
+            .chain(self.attached_node_draw_indices.iter())
+            .map(global_end)
+            .collect()
+    }
+
+    /// Gets the outline of the polyfill in global coordinates, using the positions in `pose`.
+    ///
+    /// Like `get_global_vertices`, the outline runs through the end of the anchor node and then the ends of the attached nodes.
+    /// Where two consecutive vertices are the start and end of a curved node (see `Node::centerline`), the edge between them
+    /// follows the curve, split into `2 * segment_curve_polyfill_precision` straight pieces. Nodes that do not exist are skipped.
+    pub fn get_global_outline(&self, pose: &Pose, stickfigure: &Stickfigure) -> Vec<Vec2> {
+        let draw_indices: Vec<DrawOrderIndex> = core::iter::once(self.anchor_node_draw_index)
+            .chain(self.attached_node_draw_indices.iter().copied())
+            .filter(|draw_index| pose.get(*draw_index).is_some())
+            .collect();
 
-        let anchor_position = anchor_node.borrow().get_global_end(stickfigure);
+        let mut outline = Vec::new();
+        for (i, draw_index) in draw_indices.iter().enumerate() {
+            let previous = draw_indices[(i + draw_indices.len() - 1) % draw_indices.len()];
+
+            let curve = |from: DrawOrderIndex, to: DrawOrderIndex| {
+                let node = stickfigure.get_node(to)?;
+                if stickfigure.get_parent(to) != Some(from) {
+                    return None;
+                }
+                let steps = 2 * node.segment_curve_polyfill_precision.max(1) as usize;
+                let centerline = node.centerline_with_steps(pose, Some(steps));
+                Some(centerline[1..centerline.len().saturating_sub(1)].to_vec())
+            };
+
+            if let Some(points) = curve(previous, *draw_index) {
+                outline.extend(points);
+            } else if let Some(points) = curve(*draw_index, previous) {
+                outline.extend(points.into_iter().rev());
+            }
+
+            if let Some(transform) = pose.get(*draw_index) {
+                outline.push(transform.end);
+            }
+        }
 
-        let nodes = self.attached_node_draw_indices.iter().map(|id| stickfigure.get_node(*id).expect("this is an internal library error because all attached node draw indices should always be valid"));
+        outline
+    }
 
-        let mut node_positions = nodes.map(|node| node.borrow().get_global_end(stickfigure)).collect::<Vec<Vec2>>();
+    /// Gets the color the polyfill is drawn in: its own `color` if `use_polyfill_color` is set, otherwise the display color of its anchor node.
+    pub fn get_display_color(&self, stickfigure: &Stickfigure) -> Color {
+        if self.use_polyfill_color {
+            return self.color;
+        }
 
-        node_positions.insert(0, anchor_position);
+        stickfigure
+            .get_node(self.anchor_node_draw_index)
+            .map(|anchor_node| anchor_node.get_display_color(stickfigure))
+            .unwrap_or(self.color)
+    }
 
-        node_positions
+    /// Triangulates the area of the polyfill.
+    ///
+    /// # Parameters
+    ///
+    /// * `pose` - Positions of the nodes, from `Stickfigure::compute_pose`.
+    /// * `fill_rule` - How parts of the outline that cross over other parts are filled.
+    /// * `stickfigure` - The `Stickfigure` the polyfill belongs to.
+    ///
+    /// # Returns
+    ///
+    /// A `PolyfillMesh` with the triangles of the outline from `get_global_outline` and the resolved display color.
+    pub fn get_mesh(&self, pose: &Pose, fill_rule: FillRule, stickfigure: &Stickfigure) -> PolyfillMesh {
+        PolyfillMesh {
+            triangles: triangulate(&[self.get_global_outline(pose, stickfigure)], fill_rule),
+            color: self.get_display_color(stickfigure),
+        }
     }
 
     // below methods are commented out because I don't know if they'd be worth existing since there would have to be some kind of special return for if the provided index to insert after/before is invalid.. which kind of defeats the point of the method. At that point just handle the error of the non try versions lol
@@ -276,7 +353,7 @@ impl Polyfill {
     pub fn try_remove_attached_node_draw_indices(
         &mut self,
         draw_indices: Vec<DrawOrderIndex>,
-        stickfigure: Stickfigure,
+        stickfigure: &Stickfigure,
     ) -> Vec<DrawOrderIndex> {
         let missing_indices = stickfigure.missing_draw_indices(&draw_indices);
 
@@ -298,3 +375,79 @@ impl Polyfill {
         missing_indices
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+
+    /// A node along the x axis and one turned back up along the y axis from its end, with a polyfill through their ends
+    /// and the root node: the triangle (100, 0), (100, 100), (0, 0).
+    fn triangle() -> (Stickfigure, DrawOrderIndex) {
+        let mut stickfigure = Stickfigure::new();
+        let arm = stickfigure.add_node(segment(100.0, 0.0), DrawOrderIndex(0)).unwrap();
+        let hand = stickfigure.add_node(segment(100.0, 90.0), arm).unwrap();
+        stickfigure.add_polyfill(Polyfill {
+            anchor_node_draw_index: arm,
+            attached_node_draw_indices: Vec::from([hand, DrawOrderIndex(0)]),
+            ..Default::default()
+        });
+        (stickfigure, hand)
+    }
+
+    fn area(triangles: &[[Vec2; 3]]) -> f32 {
+        triangles.iter().map(|[a, b, c]| (*b - *a).perp_dot(*c - *a).abs() / 2.0).sum()
+    }
+
+    #[test]
+    fn outline_runs_through_the_node_ends() {
+        let (stickfigure, _) = triangle();
+        let pose = stickfigure.compute_pose();
+
+        let outline = stickfigure.polyfills[0].get_global_outline(&pose, &stickfigure);
+
+        let expected = [Vec2::new(100.0, 0.0), Vec2::new(100.0, 100.0), Vec2::ZERO];
+        assert_eq!(outline.len(), expected.len());
+        for (point, expected) in outline.iter().zip(expected) {
+            assert!(point.distance(expected) < 1e-3, "expected {expected}, got {point}");
+        }
+    }
+
+    #[test]
+    fn outline_follows_curved_nodes() {
+        let (mut stickfigure, hand) = triangle();
+        stickfigure
+            .update_node(hand, |node| {
+                node.segment_curve_radius_and_default_curve_radius = 100;
+                node.segment_curve_polyfill_precision = 2;
+            })
+            .unwrap();
+        let pose = stickfigure.compute_pose();
+
+        let outline = stickfigure.polyfills[0].get_global_outline(&pose, &stickfigure);
+
+        // Split into 4 pieces, so 3 points between the start and end of the curved node.
+        assert_eq!(outline.len(), 6);
+        for point in &outline[1..4] {
+            assert!((point.x - 100.0).abs() > 1.0, "{point} is on the straight edge");
+            assert!(point.y > 0.0 && point.y < 100.0);
+        }
+    }
+
+    #[test]
+    fn mesh_fills_the_outline_in_the_polyfill_color() {
+        let (mut stickfigure, _) = triangle();
+        let pose = stickfigure.compute_pose();
+        let mesh = stickfigure.polyfills[0].get_mesh(&pose, FillRule::NonZero, &stickfigure);
+
+        assert!((area(&mesh.triangles) - 5000.0).abs() < 1e-2);
+        assert_eq!(mesh.color, stickfigure.get_node(DrawOrderIndex(1)).unwrap().get_display_color(&stickfigure));
+
+        let red = Color::from_rgb(255, 0, 0);
+        stickfigure.polyfills[0].color = red;
+        stickfigure.polyfills[0].use_polyfill_color = true;
+        let mesh = stickfigure.polyfills[0].get_mesh(&pose, FillRule::EvenOdd, &stickfigure);
+        assert!((area(&mesh.triangles) - 5000.0).abs() < 1e-2);
+        assert_eq!(mesh.color, red);
+    }
+}
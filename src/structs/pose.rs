@@ -0,0 +1,365 @@
+use glam::Vec2;
+use hashbrown::HashMap;
+use petgraph::graph::NodeIndex;
+use petgraph::Direction;
+extern crate alloc;
+use alloc::vec::Vec;
+
+use crate::{ConnectorData, ConnectorMethod, DrawOrderIndex, Node, NodeType, Stickfigure};
+
+/// World transform of a single node, as computed by `Stickfigure::compute_pose`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeTransform {
+    /// Global position of the start of the node (the end of its parent).
+    pub start: Vec2,
+    /// Global position of the end of the node.
+    pub end: Vec2,
+    /// Global angle of the node in degrees.
+    pub angle: f32,
+    /// Effective scale of the node: the stickfigure's scale times the segment scale of this node and every ancestor.
+    pub scale: f32,
+    /// Drawn thickness of the node, i.e. its thickness times its effective scale.
+    pub thickness: f32,
+}
+
+impl Default for NodeTransform {
+    fn default() -> Self {
+        Self::ORIGIN
+    }
+}
+
+impl NodeTransform {
+    /// Transform that the root node of an unscaled stickfigure is placed relative to.
+    pub const ORIGIN: Self = Self {
+        start: Vec2::ZERO,
+        end: Vec2::ZERO,
+        angle: 0.0,
+        scale: 1.0,
+        thickness: 0.0,
+    };
+
+    /// Transform that the root node of a stickfigure with the given figure-wide scale is placed relative to.
+    pub fn origin(stickfigure_scale: f32) -> Self {
+        Self {
+            scale: stickfigure_scale,
+            ..Self::ORIGIN
+        }
+    }
+
+    /// Places `node` at the end of the node whose transform is `parent`.
+    ///
+    /// Scale follows these rules:
+    /// * A node's segment scale (`scale`) only applies if `use_segment_scale` is set.
+    /// * Segment scale is inherited: a node's effective scale is its parent's effective scale times its own segment scale,
+    ///   so scaling a node also scales everything attached to it.
+    /// * The stickfigure's `scale` applies to every node, through the transform of the root node's parent (see `origin`).
+    /// * Both the drawn length and the drawn thickness of a node are multiplied by its effective scale.
+    pub fn from_parent(parent: &NodeTransform, node: &Node) -> Self {
+        let angle = parent.angle + node.local_angle;
+        let segment_scale = if node.use_segment_scale { node.scale } else { 1.0 };
+        let scale = parent.scale * segment_scale;
+        let start = parent.end;
+
+        let end = if node.node_type == NodeType::RootNode {
+            start
+        } else {
+            let angle_radians = angle.to_radians();
+            let direction = Vec2 {
+                x: libm::cosf(angle_radians),
+                y: libm::sinf(angle_radians),
+            };
+            start + direction * node.length * scale
+        };
+
+        Self {
+            start,
+            end,
+            angle,
+            scale,
+            thickness: node.thickness as f32 * scale,
+        }
+    }
+
+    /// Places a connector `node` that starts at the end of the node whose transform is `parent` and points at `target`,
+    /// the end of the node it connects to, moved by `local_x` and `local_y` times the node's effective scale.
+    ///
+    /// With `ConnectorMethod::ByPercent` the connector ends `percent` of the way along the span from its start to
+    /// `target`, where 0 is its start and 1 is `target`. With `ConnectorMethod::ByValue` it ends `value` units along the span,
+    /// scaled by the node's effective scale and `smart_stretch_ancestral_value`. If `reversed` is set, the distance is
+    /// measured back from `target` instead. The connector's angle always follows the span.
+    pub fn from_parent_to_connector_end(
+        parent: &NodeTransform,
+        node: &Node,
+        connector_data: &ConnectorData,
+        target: Vec2,
+    ) -> Self {
+        let ordinary = Self::from_parent(parent, node);
+        let start = ordinary.start;
+        let target = target + Vec2::new(connector_data.local_x, connector_data.local_y) * ordinary.scale;
+        let span = target - start;
+        let span_length = span.length();
+
+        let (direction, angle) = if span_length > f32::EPSILON {
+            (span / span_length, libm::atan2f(span.y, span.x).to_degrees())
+        } else {
+            let angle_radians = ordinary.angle.to_radians();
+            (
+                Vec2 {
+                    x: libm::cosf(angle_radians),
+                    y: libm::sinf(angle_radians),
+                },
+                ordinary.angle,
+            )
+        };
+
+        let distance = match connector_data.method {
+            ConnectorMethod::ByPercent => connector_data.percent * span_length,
+            ConnectorMethod::ByValue => {
+                connector_data.value * ordinary.scale * connector_data.smart_stretch_ancestral_value
+            }
+        };
+
+        let end = if connector_data.reversed {
+            target - direction * distance
+        } else {
+            start + direction * distance
+        };
+
+        Self {
+            end,
+            angle,
+            ..ordinary
+        }
+    }
+
+    /// Offset from the start to the end of the node.
+    pub fn offset(&self) -> Vec2 {
+        self.end - self.start
+    }
+}
+
+/// World transforms of every node of a `Stickfigure`, computed in a single top-down traversal.
+///
+/// A `Pose` is a snapshot: it is not updated when the `Stickfigure` it was computed from changes.
+#[derive(Debug, Clone, Default)]
+pub struct Pose {
+    transforms: HashMap<DrawOrderIndex, NodeTransform>,
+    pub(crate) version: i32,
+    pub(crate) build: i32,
+}
+
+impl Pose {
+    /// Gets the world transform of the node at the specified `DrawOrderIndex`.
+    pub fn get(&self, draw_index: DrawOrderIndex) -> Option<&NodeTransform> {
+        self.transforms.get(&draw_index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (DrawOrderIndex, &NodeTransform)> {
+        self.transforms
+            .iter()
+            .map(|(draw_index, transform)| (*draw_index, transform))
+    }
+
+    pub fn len(&self) -> usize {
+        self.transforms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+}
+
+impl Stickfigure {
+    /// Computes the world start, end, angle and effective scale of every node.
+    ///
+    /// Every node is visited once, parents before children, so this is O(n). Prefer it over calling
+    /// `Node::get_global_start`/`get_global_end` for many nodes, which walk each node's ancestors separately.
+    ///
+    /// A connector node is placed once the node it connects to has been (see `NodeTransform::from_parent_to_connector_end`).
+    /// Connectors that can never be placed that way, because they connect to a node that does not exist or to a node
+    /// that itself depends on the connector, are placed like ordinary nodes instead.
+    pub fn compute_pose(&self) -> Pose {
+        let mut transforms: HashMap<DrawOrderIndex, NodeTransform> = HashMap::with_capacity(self.nodes.node_count());
+        let mut stack: Vec<(NodeIndex, NodeTransform, bool)> = self
+            .nodes
+            .externals(Direction::Incoming)
+            .map(|node_index| (node_index, NodeTransform::origin(self.scale), false))
+            .collect();
+        let mut waiting_connectors: Vec<(NodeIndex, NodeTransform)> = Vec::new();
+
+        loop {
+            while let Some((node_index, parent_transform, place_as_ordinary)) = stack.pop() {
+                let node = &self.nodes[node_index];
+
+                let transform = match (&node.connector_data, place_as_ordinary) {
+                    (Some(connector_data), false) => match transforms.get(&connector_data.end_node_draw_index) {
+                        Some(end_node_transform) => NodeTransform::from_parent_to_connector_end(
+                            &parent_transform,
+                            node,
+                            connector_data,
+                            end_node_transform.end,
+                        ),
+                        None => {
+                            waiting_connectors.push((node_index, parent_transform));
+                            continue;
+                        }
+                    },
+                    _ => NodeTransform::from_parent(&parent_transform, node),
+                };
+
+                transforms.insert(self.draw_order_from_node_index(node_index), transform);
+
+                for child_index in self.nodes.neighbors_directed(node_index, Direction::Outgoing) {
+                    stack.push((child_index, transform, false));
+                }
+            }
+
+            if waiting_connectors.is_empty() {
+                break;
+            }
+
+            let (ready, waiting): (Vec<_>, Vec<_>) = waiting_connectors.drain(..).partition(|(node_index, _)| {
+                self.nodes[*node_index]
+                    .connector_data
+                    .as_ref()
+                    .is_some_and(|connector_data| transforms.contains_key(&connector_data.end_node_draw_index))
+            });
+            waiting_connectors = waiting;
+
+            if ready.is_empty() {
+                // No waiting connector can be placed, so break the deadlock with one of them.
+                if let Some((node_index, parent_transform)) = waiting_connectors.pop() {
+                    stack.push((node_index, parent_transform, true));
+                }
+            } else {
+                stack.extend(
+                    ready
+                        .into_iter()
+                        .map(|(node_index, parent_transform)| (node_index, parent_transform, false)),
+                );
+            }
+        }
+
+        Pose {
+            transforms,
+            version: self.version,
+            build: self.build,
+        }
+    }
+
+    /// Computes the world transform of the node at the specified `DrawOrderIndex`.
+    ///
+    /// Only the transforms it depends on are computed: those of the node's ancestors and, for any connector among them,
+    /// of the node the connector connects to. Connectors that depend on each other in a cycle fall back to `compute_pose`,
+    /// which decides how they are placed.
+    pub(crate) fn compute_transform(&self, draw_index: DrawOrderIndex) -> NodeTransform {
+        let mut transforms = HashMap::new();
+        match self.resolve_transform(draw_index, &mut transforms, &mut Vec::new()) {
+            Some(transform) => transform,
+            None => self.compute_pose().get(draw_index).copied().unwrap_or_default(),
+        }
+    }
+
+    /// Computes the world transform of the parent of the node at the specified `DrawOrderIndex`, like `compute_transform`.
+    /// Returns `NodeTransform::origin` for the root node.
+    pub(crate) fn compute_parent_transform(&self, draw_index: DrawOrderIndex) -> NodeTransform {
+        match self.get_parent(draw_index) {
+            Some(parent_draw_index) => self.compute_transform(parent_draw_index),
+            None => NodeTransform::origin(self.scale),
+        }
+    }
+
+    /// Computes the transform of a node and the transforms it depends on, adding them to `transforms`.
+    ///
+    /// # Returns
+    ///
+    /// The node's transform, or `None` if it depends on a connector that depends on it in turn. `visiting` holds the
+    /// nodes whose transforms are being computed further up the call stack.
+    fn resolve_transform(
+        &self,
+        draw_index: DrawOrderIndex,
+        transforms: &mut HashMap<DrawOrderIndex, NodeTransform>,
+        visiting: &mut Vec<DrawOrderIndex>,
+    ) -> Option<NodeTransform> {
+        if let Some(transform) = transforms.get(&draw_index) {
+            return Some(*transform);
+        }
+        if visiting.contains(&draw_index) {
+            return None;
+        }
+        visiting.push(draw_index);
+
+        let parent_transform = match self.get_parent(draw_index) {
+            Some(parent_draw_index) => self.resolve_transform(parent_draw_index, transforms, visiting)?,
+            None => NodeTransform::origin(self.scale),
+        };
+
+        let transform = match self.get_node(draw_index) {
+            Some(node) => match &node.connector_data {
+                Some(connector_data) if self.draw_index_exists(connector_data.end_node_draw_index) => {
+                    let target = self
+                        .resolve_transform(connector_data.end_node_draw_index, transforms, visiting)?
+                        .end;
+                    NodeTransform::from_parent_to_connector_end(&parent_transform, node, connector_data, target)
+                }
+                _ => NodeTransform::from_parent(&parent_transform, node),
+            },
+            None => parent_transform,
+        };
+
+        visiting.pop();
+        transforms.insert(draw_index, transform);
+        Some(transform)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+    use crate::ConnectorData;
+
+    fn assert_getters_match_pose(stickfigure: &Stickfigure) {
+        let pose = stickfigure.compute_pose();
+        for draw_index in stickfigure.get_all_node_indices() {
+            let node = stickfigure.get_node(draw_index).unwrap();
+            let transform = pose.get(draw_index).unwrap();
+            assert!(node.get_global_end(stickfigure).distance(transform.end) < 1e-3);
+            assert!(node.get_global_start(stickfigure).distance(transform.start) < 1e-3);
+        }
+    }
+
+    #[test]
+    fn getters_match_pose_with_connectors() {
+        let mut stickfigure = Stickfigure::new();
+        let arm = stickfigure.add_node(segment(50.0, 0.0), DrawOrderIndex(0)).unwrap();
+        let hand = stickfigure.add_node(segment(20.0, 30.0), arm).unwrap();
+        let leg = stickfigure.add_node(segment(60.0, 90.0), DrawOrderIndex(0)).unwrap();
+        let connector = stickfigure
+            .add_connector(segment(10.0, 0.0), leg, hand, ConnectorData::default())
+            .unwrap();
+        stickfigure.add_node(segment(15.0, 45.0), connector).unwrap();
+
+        assert_getters_match_pose(&stickfigure);
+    }
+
+    #[test]
+    fn getters_match_pose_with_connectors_that_depend_on_each_other() {
+        let mut stickfigure = Stickfigure::new();
+        let first = stickfigure.add_node(segment(50.0, 0.0), DrawOrderIndex(0)).unwrap();
+        let second = stickfigure.add_node(segment(50.0, 90.0), DrawOrderIndex(0)).unwrap();
+        let first_connector = stickfigure
+            .add_connector(segment(10.0, 0.0), first, second, ConnectorData::default())
+            .unwrap();
+        let second_connector = stickfigure
+            .add_connector(segment(10.0, 0.0), second, first_connector, ConnectorData::default())
+            .unwrap();
+        stickfigure
+            .update_node(first_connector, |node| {
+                node.connector_data.as_mut().unwrap().end_node_draw_index = second_connector
+            })
+            .unwrap();
+
+        assert_getters_match_pose(&stickfigure);
+    }
+}
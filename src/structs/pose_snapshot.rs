@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+extern crate alloc;
+use alloc::{collections::BTreeMap, format, string::ToString, vec::Vec};
+
+use crate::{AngleInterpolation, Color, DrawOrderIndex, Node, Stickfigure, StickfigureError};
+
+/// The posable properties of one node, as stored in a `PoseSnapshot`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NodeState {
+    pub local_angle: f32,
+    pub length: f32,
+    pub scale: f32,
+    pub thickness: i32,
+    pub segment_curve_radius_and_default_curve_radius: i32,
+    pub color: Color,
+    pub gradient_color: Color,
+    pub circle_outline_color: Color,
+    pub use_segment_color: bool,
+    pub use_segment_scale: bool,
+    pub use_circle_outline: bool,
+    pub circle_is_hollow: bool,
+    pub use_gradient: bool,
+    pub reverse_gradient: bool,
+}
+
+impl NodeState {
+    /// Copies the posable properties of `node`.
+    pub fn from_node(node: &Node) -> Self {
+        Self {
+            local_angle: node.local_angle,
+            length: node.length,
+            scale: node.scale,
+            thickness: node.thickness,
+            segment_curve_radius_and_default_curve_radius: node.segment_curve_radius_and_default_curve_radius,
+            color: node.color,
+            gradient_color: node.gradient_color,
+            circle_outline_color: node.circle_outline_color,
+            use_segment_color: node.use_segment_color,
+            use_segment_scale: node.use_segment_scale,
+            use_circle_outline: node.use_circle_outline,
+            circle_is_hollow: node.circle_is_hollow,
+            use_gradient: node.use_gradient,
+            reverse_gradient: node.reverse_gradient,
+        }
+    }
+
+    /// Writes these properties to `node`, leaving everything else about it alone.
+    ///
+    /// `local_angle` is not written here: it goes through `Stickfigure::set_local_angles` so angle locks are kept.
+    pub(crate) fn write_to(&self, node: &mut Node) {
+        node.length = self.length;
+        node.scale = self.scale;
+        node.thickness = self.thickness;
+        node.segment_curve_radius_and_default_curve_radius = self.segment_curve_radius_and_default_curve_radius;
+        node.color = self.color;
+        node.gradient_color = self.gradient_color;
+        node.circle_outline_color = self.circle_outline_color;
+        node.use_segment_color = self.use_segment_color;
+        node.use_segment_scale = self.use_segment_scale;
+        node.use_circle_outline = self.use_circle_outline;
+        node.circle_is_hollow = self.circle_is_hollow;
+        node.use_gradient = self.use_gradient;
+        node.reverse_gradient = self.reverse_gradient;
+    }
+
+    /// Interpolates between this state (at `t` = 0) and `other` (at `t` = 1).
+    ///
+    /// Numbers and color channels are interpolated linearly, and integers are rounded. Angles are interpolated as `angle` says.
+    /// Flags switch from this state's to `other`'s halfway.
+    pub(crate) fn interpolate(&self, other: &NodeState, t: f32, angle: AngleInterpolation) -> NodeState {
+        let flags = if t < 0.5 { self } else { other };
+
+        let angle_delta = match angle {
+            AngleInterpolation::Linear => other.local_angle - self.local_angle,
+            AngleInterpolation::ShortestArc => libm::remainderf(other.local_angle - self.local_angle, 360.0),
+        };
+
+        NodeState {
+            local_angle: self.local_angle + angle_delta * t,
+            length: lerp(self.length, other.length, t),
+            scale: lerp(self.scale, other.scale, t),
+            thickness: lerp_integer(self.thickness, other.thickness, t),
+            segment_curve_radius_and_default_curve_radius: lerp_integer(
+                self.segment_curve_radius_and_default_curve_radius,
+                other.segment_curve_radius_and_default_curve_radius,
+                t,
+            ),
+            color: lerp_color(self.color, other.color, t),
+            gradient_color: lerp_color(self.gradient_color, other.gradient_color, t),
+            circle_outline_color: lerp_color(self.circle_outline_color, other.circle_outline_color, t),
+            use_segment_color: flags.use_segment_color,
+            use_segment_scale: flags.use_segment_scale,
+            use_circle_outline: flags.use_circle_outline,
+            circle_is_hollow: flags.circle_is_hollow,
+            use_gradient: flags.use_gradient,
+            reverse_gradient: flags.reverse_gradient,
+        }
+    }
+}
+
+/// The posable properties of the nodes of a stickfigure, keyed by draw order index.
+///
+/// Where `Pose` holds the world transforms computed from a stickfigure, a `PoseSnapshot` holds the per-node values that
+/// produce them, so it can be stored, edited and interpolated on its own.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoseSnapshot {
+    pub nodes: BTreeMap<DrawOrderIndex, NodeState>,
+}
+
+impl PoseSnapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Captures the posable properties of every node of `stickfigure`, the root node included.
+    pub fn capture(stickfigure: &Stickfigure) -> Self {
+        Self {
+            nodes: stickfigure
+                .get_all_node_indices()
+                .into_iter()
+                .filter_map(|draw_index| stickfigure.get_node(draw_index).map(|node| (draw_index, NodeState::from_node(node))))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, draw_index: DrawOrderIndex) -> Option<&NodeState> {
+        self.nodes.get(&draw_index)
+    }
+
+    pub fn insert(&mut self, draw_index: DrawOrderIndex, state: NodeState) -> Option<NodeState> {
+        self.nodes.insert(draw_index, state)
+    }
+
+    /// Gets the changes that turn this snapshot into `other`.
+    ///
+    /// # Returns
+    ///
+    /// A `PoseSnapshot` with the nodes of `other` whose state differs from, or is missing in, this snapshot.
+    /// `overlay`ing it onto this snapshot gives back the nodes of `other`.
+    pub fn diff(&self, other: &PoseSnapshot) -> PoseSnapshot {
+        PoseSnapshot {
+            nodes: other
+                .nodes
+                .iter()
+                .filter(|(draw_index, state)| self.nodes.get(draw_index) != Some(*state))
+                .map(|(draw_index, state)| (*draw_index, *state))
+                .collect(),
+        }
+    }
+
+    /// Replaces the state of every node in `other`, and adds the ones this snapshot doesn't have.
+    pub fn overlay(&mut self, other: &PoseSnapshot) {
+        self.nodes.extend(other.nodes.iter().map(|(draw_index, state)| (*draw_index, *state)));
+    }
+
+    /// Blends this snapshot with `other`.
+    ///
+    /// Nodes in both snapshots are interpolated like keyframes of an `Animation`, with angles turning the shorter way round.
+    /// Nodes in only one snapshot are kept as they are.
+    ///
+    /// # Parameters
+    ///
+    /// * `other` - Snapshot to blend towards.
+    /// * `weight` - How much of `other` to use, from 0 (this snapshot) to 1 (`other`).
+    pub fn blend(&self, other: &PoseSnapshot, weight: f32) -> PoseSnapshot {
+        let weight = weight.clamp(0.0, 1.0);
+        let mut blended = other.clone();
+        for (draw_index, state) in &self.nodes {
+            let blended_state = match other.nodes.get(draw_index) {
+                Some(other_state) => state.interpolate(other_state, weight, AngleInterpolation::ShortestArc),
+                None => *state,
+            };
+            blended.nodes.insert(*draw_index, blended_state);
+        }
+        blended
+    }
+
+    /// Writes every state in this snapshot to the node at its draw order index. Nodes that don't exist are skipped.
+    pub(crate) fn write_to(&self, stickfigure: &mut Stickfigure) {
+        for (draw_index, state) in &self.nodes {
+            if let Some(node) = stickfigure.get_node_mut(*draw_index) {
+                state.write_to(node);
+            }
+        }
+        stickfigure.set_local_angles(&self.local_angles());
+    }
+
+    fn local_angles(&self) -> Vec<(DrawOrderIndex, f32)> {
+        self.nodes
+            .iter()
+            .map(|(draw_index, state)| (*draw_index, state.local_angle))
+            .collect()
+    }
+}
+
+impl Stickfigure {
+    /// Sets the posable properties of the nodes in `pose`, e.g. one made with `PoseSnapshot::capture`. Nodes not in `pose`
+    /// are left alone, apart from angle locked ones, which keep their locks as with `set_angle`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error without changing anything if `pose` has nodes that the stickfigure doesn't
+    /// (`StickfigureError::InvalidDrawIndices`), or if any node would use something the version and build of the
+    /// stickfigure can't load (see `update_node`).
+    pub fn apply_pose_snapshot(&mut self, pose: &PoseSnapshot) -> Result<(), StickfigureError> {
+        let draw_indices: Vec<DrawOrderIndex> = pose.nodes.keys().copied().collect();
+        let missing_indices = self.missing_draw_indices(&draw_indices);
+        if !missing_indices.is_empty() {
+            return Err(StickfigureError::InvalidDrawIndices(
+                format!("{:?}", missing_indices),
+                "Cannot apply pose with nodes that are not in the stickfigure.".to_string(),
+            ));
+        }
+
+        let capabilities = self.capabilities();
+        for (draw_index, state) in &pose.nodes {
+            if let Some(node) = self.get_node(*draw_index) {
+                let mut node = node.clone();
+                state.write_to(&mut node);
+                capabilities.check_node(&node)?;
+            }
+        }
+
+        for (draw_index, state) in &pose.nodes {
+            self.update_node(*draw_index, |node| state.write_to(node))?;
+        }
+        self.set_local_angles(&pose.local_angles());
+
+        Ok(())
+    }
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+fn lerp_integer(from: i32, to: i32, t: f32) -> i32 {
+    libm::roundf(lerp(from as f32, to as f32, t)) as i32
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    let channel = |from: u8, to: u8| libm::roundf(lerp(from as f32, to as f32, t)).clamp(0.0, 255.0) as u8;
+    Color::from_rgba(
+        channel(from.red, to.red),
+        channel(from.green, to.green),
+        channel(from.blue, to.blue),
+        channel(from.alpha, to.alpha),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+    use crate::AngleLockMode;
+
+    #[test]
+    fn applying_a_snapshot_keeps_angle_locks_of_nodes_not_in_it() {
+        let mut stickfigure = Stickfigure::new();
+        let arm = stickfigure.add_node(segment(10.0, 0.0), DrawOrderIndex(0)).unwrap();
+        let hand = stickfigure.add_node(segment(10.0, 30.0), arm).unwrap();
+        stickfigure
+            .update_node(hand, |node| node.angle_lock_mode = AngleLockMode::Absolute)
+            .unwrap();
+
+        let mut snapshot = PoseSnapshot::new();
+        let mut arm_state = NodeState::from_node(stickfigure.get_node(arm).unwrap());
+        arm_state.local_angle = 90.0;
+        snapshot.insert(arm, arm_state);
+        stickfigure.apply_pose_snapshot(&snapshot).unwrap();
+
+        let pose = stickfigure.compute_pose();
+        assert_eq!(pose.get(arm).unwrap().angle, 90.0);
+        assert_eq!(pose.get(hand).unwrap().angle, 30.0);
+    }
+
+    #[test]
+    fn applying_a_snapshot_with_unsupported_properties_changes_nothing() {
+        let mut stickfigure = Stickfigure::new();
+        let arm = stickfigure.add_node(segment(10.0, 0.0), DrawOrderIndex(0)).unwrap();
+        let hand = stickfigure.add_node(segment(10.0, 30.0), arm).unwrap();
+        stickfigure.set_version_and_build(170, 1).unwrap();
+
+        let mut snapshot = PoseSnapshot::capture(&stickfigure);
+        snapshot.nodes.get_mut(&arm).unwrap().local_angle = 90.0;
+        snapshot.nodes.get_mut(&hand).unwrap().use_gradient = true;
+
+        assert!(stickfigure.apply_pose_snapshot(&snapshot).is_err());
+        assert_eq!(stickfigure.get_node(arm).unwrap().local_angle, 0.0);
+        assert!(!stickfigure.get_node(hand).unwrap().use_gradient);
+    }
+}
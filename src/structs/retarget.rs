@@ -0,0 +1,133 @@
+extern crate alloc;
+use alloc::{format, string::ToString, vec::Vec};
+
+use crate::{match_nodes, DrawOrderIndex, Mapping, PoseSnapshot, Stickfigure, StickfigureError};
+
+impl Stickfigure {
+    /// Poses this stickfigure like `source` is posed in `source_pose`, even if the two are rigged differently.
+    ///
+    /// Each node of this stickfigure that is paired with a node of `source` is turned to point in the same global
+    /// direction as that node. Nodes that aren't paired keep their local angle, so they follow their parent. Lengths
+    /// and everything else about this stickfigure are left as they are.
+    ///
+    /// # Parameters
+    ///
+    /// * `source` - The stickfigure the pose was made for.
+    /// * `source_pose` - Pose of `source` to copy. Nodes of `source` not in it keep their current properties.
+    /// * `mapping` - Pairs of nodes, from `source` to this stickfigure. If `None`, it is derived with `match_nodes`.
+    ///
+    /// # Returns
+    ///
+    /// A `PoseSnapshot` of every node of this stickfigure, to be applied with `apply_pose_snapshot`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StickfigureError::InvalidDrawIndices` if `source_pose` has nodes that `source` doesn't, or if `mapping`
+    /// pairs nodes that don't exist.
+    pub fn retarget_pose(
+        &self,
+        source: &Stickfigure,
+        source_pose: &PoseSnapshot,
+        mapping: Option<&Mapping>,
+    ) -> Result<PoseSnapshot, StickfigureError> {
+        let derived_mapping;
+        let mapping = match mapping {
+            Some(mapping) => mapping,
+            None => {
+                derived_mapping = match_nodes(source, self);
+                &derived_mapping
+            }
+        };
+
+        let sources: Vec<DrawOrderIndex> = mapping.iter().map(|(source, _)| source).collect();
+        let targets: Vec<DrawOrderIndex> = mapping.iter().map(|(_, target)| target).collect();
+        let missing_sources = source.missing_draw_indices(&sources);
+        let missing_targets = self.missing_draw_indices(&targets);
+        if !missing_sources.is_empty() || !missing_targets.is_empty() {
+            return Err(StickfigureError::InvalidDrawIndices(
+                format!("{:?}", [missing_sources, missing_targets].concat()),
+                "Cannot retarget with a mapping of nodes that do not exist.".to_string(),
+            ));
+        }
+
+        let mut posed_source = source.clone();
+        posed_source.apply_pose_snapshot(source_pose)?;
+        let source_transforms = posed_source.compute_pose();
+
+        let mut pose = PoseSnapshot::capture(self);
+        let mut stack: Vec<(DrawOrderIndex, f32)> = Vec::from([(DrawOrderIndex(0), 0.0)]);
+
+        while let Some((draw_index, parent_global_angle)) = stack.pop() {
+            let Some(state) = pose.nodes.get_mut(&draw_index) else {
+                continue;
+            };
+
+            let source_angle = mapping
+                .source_of(draw_index)
+                .and_then(|source| source_transforms.get(source))
+                .map(|transform| transform.angle);
+            if let Some(source_angle) = source_angle {
+                let turn = libm::remainderf(source_angle - parent_global_angle - state.local_angle, 360.0);
+                state.local_angle += turn;
+            }
+
+            let global_angle = parent_global_angle + state.local_angle;
+            for child in self.get_children(draw_index) {
+                stack.push((child, global_angle));
+            }
+        }
+
+        Ok(pose)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+
+    fn global_angle(stickfigure: &Stickfigure, draw_index: DrawOrderIndex) -> f32 {
+        stickfigure.compute_pose().get(draw_index).unwrap().angle
+    }
+
+    fn assert_angle_eq(actual: f32, expected: f32) {
+        assert!(libm::remainderf(actual - expected, 360.0).abs() < 1e-3, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn keeps_world_space_directions_across_rigs_of_different_shapes() {
+        let mut source = Stickfigure::new();
+        let source_upper = source.add_node(segment(50.0, 0.0), DrawOrderIndex(0)).unwrap();
+        let source_lower = source.add_node(segment(50.0, 0.0), source_upper).unwrap();
+        source.set_angle(source_upper, 90.0).unwrap();
+        source.set_angle(source_lower, 45.0).unwrap();
+        let source_pose = PoseSnapshot::capture(&source);
+
+        // Different lengths and rest angles, a joint the source doesn't have, and a hand that isn't paired.
+        let mut target = Stickfigure::new();
+        let target_upper = target.add_node(segment(20.0, 30.0), DrawOrderIndex(0)).unwrap();
+        let target_elbow = target.add_node(segment(5.0, 200.0), target_upper).unwrap();
+        let target_lower = target.add_node(segment(80.0, -60.0), target_elbow).unwrap();
+        let target_hand = target.add_node(segment(10.0, 10.0), target_lower).unwrap();
+        let mapping: Mapping = [(source_upper, target_upper), (source_lower, target_lower)].into_iter().collect();
+
+        let pose = target.retarget_pose(&source, &source_pose, Some(&mapping)).unwrap();
+        target.apply_pose_snapshot(&pose).unwrap();
+
+        assert_angle_eq(global_angle(&target, target_upper), 90.0);
+        assert_angle_eq(global_angle(&target, target_lower), 135.0);
+        assert_angle_eq(global_angle(&target, target_hand), 145.0);
+        assert_eq!(target.get_node(target_elbow).unwrap().local_angle, 200.0);
+        assert_eq!(target.get_node(target_lower).unwrap().length, 80.0);
+    }
+
+    #[test]
+    fn refuses_mappings_of_nodes_that_do_not_exist() {
+        let stickfigure = Stickfigure::new();
+        let mapping: Mapping = [(DrawOrderIndex(0), DrawOrderIndex(3))].into_iter().collect();
+
+        let result = stickfigure.retarget_pose(&stickfigure, &PoseSnapshot::capture(&stickfigure), Some(&mapping));
+
+        assert!(matches!(result, Err(StickfigureError::InvalidDrawIndices(..))));
+    }
+}
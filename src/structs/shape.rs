@@ -0,0 +1,466 @@
+use core::f32::consts::{PI, TAU};
+
+use glam::Vec2;
+extern crate alloc;
+use alloc::{vec, vec::Vec};
+
+use super::pose::NodeTransform;
+use crate::{Node, NodeType, Pose};
+
+/// Number of straight edges used to approximate a full circle. Arcs use a proportional share of it.
+pub const CIRCLE_SEGMENTS: usize = 64;
+
+/// Tessellated geometry of a node, in global coordinates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeShape {
+    /// Closed outlines of the shape. The first one is the outer boundary; any others are holes, e.g. the inside of a hollow circle.
+    pub contours: Vec<Vec<Vec2>>,
+    /// Triangles covering exactly the filled area of the shape.
+    pub triangles: Vec<[Vec2; 3]>,
+}
+
+impl NodeShape {
+    fn convex(mut points: Vec<Vec2>) -> Self {
+        // Keep every outer boundary counter-clockwise, whatever order the points were generated in.
+        let signed_area: f32 = (0..points.len())
+            .map(|i| points[i].perp_dot(points[(i + 1) % points.len()]))
+            .sum();
+        if signed_area < 0.0 {
+            points.reverse();
+        }
+
+        let triangles = fan(&points);
+        Self {
+            contours: vec![points],
+            triangles,
+        }
+    }
+
+    fn ring(center: Vec2, outer_radius: f32, inner_radius: f32, start_angle: f32) -> Self {
+        if inner_radius <= 0.0 {
+            return Self::convex(circle_points(center, outer_radius, start_angle));
+        }
+
+        let outer = circle_points(center, outer_radius, start_angle);
+        let inner = circle_points(center, inner_radius, start_angle);
+        let mut triangles = Vec::with_capacity(outer.len() * 2);
+        for i in 0..outer.len() {
+            let next = (i + 1) % outer.len();
+            triangles.push([outer[i], outer[next], inner[next]]);
+            triangles.push([outer[i], inner[next], inner[i]]);
+        }
+
+        Self {
+            contours: vec![outer, inner.into_iter().rev().collect()],
+            triangles,
+        }
+    }
+}
+
+impl Node {
+    /// Tessellates this node's shape using the global positions in `pose`.
+    ///
+    /// Every node is laid out along its segment, from its start (the end of its parent) to its end, with the node's
+    /// drawn thickness across it:
+    /// * `Segment` and `RoundedSegment` are a band of the node's thickness, with round caps for `RoundedSegment`.
+    /// * `Trapezoid` is a band that narrows or widens from its start thickness to its end thickness, with each end
+    ///   optionally rounded.
+    /// * `Circle` is a ring of the node's thickness around the circle whose diameter is the segment. `FilledCircle` is that
+    ///   whole disc, or the same ring if `circle_is_hollow` is set.
+    /// * `Ellipse` spans the segment lengthwise and the node's thickness across.
+    /// * `Triangle` has its base at the start and its tip at the end, or the other way round if `triangle_upside_down` is set.
+    ///   A `TriangleType::RightTriangle` has its right angle on the side of the base given by the `right_triangle_direction`
+    ///   written for it: the left side, or the right side if `triangle_flipped` is set.
+    /// * `Polygon` is a regular polygon with `num_polygon_vertices` vertices inscribed in the circle whose diameter is the
+    ///   segment, with a vertex at the node's end.
+    /// * `RootNode` has no shape.
+    ///
+    /// Segments, rounded segments and trapezoids follow the curve described by `segment_curve_radius_and_default_curve_radius`
+    /// (see `centerline`).
+    ///
+    /// # Returns
+    ///
+    /// The node's `NodeShape`, which is empty if the node is not in `pose`.
+    pub fn shape(&self, pose: &Pose) -> NodeShape {
+        let Some(transform) = pose.get(self.draw_order_index) else {
+            return NodeShape::default();
+        };
+
+        let start = transform.start;
+        let end = transform.end;
+        let direction = direction(transform);
+        let normal = direction.perp();
+        let center = (start + end) / 2.0;
+        let half_length = start.distance(end) / 2.0;
+        let half_thickness = transform.thickness / 2.0;
+        let angle = transform.angle.to_radians();
+
+        match self.node_type {
+            NodeType::RootNode => NodeShape::default(),
+            NodeType::Segment => stroke(&self.centerline(pose), |_| half_thickness, false, false),
+            NodeType::RoundedSegment => stroke(&self.centerline(pose), |_| half_thickness, true, true),
+            NodeType::Trapezoid => {
+                // Builds before 36 store no end thickness ratio (-1) unless one was set, which means no taper.
+                let start_half = self.trapezoid_thickness_start_for(pose.version, pose.build).abs() * transform.scale / 2.0;
+                let end_half = self.trapezoid_thickness_end_for(pose.version, pose.build).abs() * transform.scale / 2.0;
+                stroke(
+                    &self.centerline(pose),
+                    |t| start_half + (end_half - start_half) * t,
+                    self.trapezoid_is_rounded_start,
+                    self.trapezoid_is_rounded_end,
+                )
+            }
+            NodeType::Circle => NodeShape::ring(center, half_length + half_thickness, half_length - half_thickness, angle),
+            NodeType::FilledCircle if self.circle_is_hollow => {
+                NodeShape::ring(center, half_length + half_thickness, half_length - half_thickness, angle)
+            }
+            NodeType::FilledCircle => NodeShape::convex(circle_points(center, half_length, angle)),
+            NodeType::Ellipse => NodeShape::convex(
+                (0..CIRCLE_SEGMENTS)
+                    .map(|i| {
+                        let t = TAU * i as f32 / CIRCLE_SEGMENTS as f32;
+                        center + direction * half_length * libm::cosf(t) + normal * half_thickness * libm::sinf(t)
+                    })
+                    .collect(),
+            ),
+            NodeType::Triangle => {
+                let (base, tip) = if self.triangle_upside_down { (end, start) } else { (start, end) };
+                let points = match self.resolved_right_triangle_direction() {
+                    0 => vec![base - normal * half_thickness, tip, base + normal * half_thickness],
+                    direction => {
+                        let side = normal * direction.signum() as f32;
+                        vec![base - side * half_thickness, tip + side * half_thickness, base + side * half_thickness]
+                    }
+                };
+                NodeShape::convex(points)
+            }
+            NodeType::Polygon => {
+                let vertices = self.num_polygon_vertices.max(3) as usize;
+                NodeShape::convex(
+                    (0..vertices)
+                        .map(|i| {
+                            let t = angle + TAU * i as f32 / vertices as f32;
+                            center + Vec2::new(libm::cosf(t), libm::sinf(t)) * half_length
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Tessellates the outline drawn around a `Circle` or `FilledCircle` node with `use_circle_outline` set,
+    /// in `circle_outline_color`.
+    ///
+    /// The outline is a ring of half the node's drawn thickness hugging the outside of the circle.
+    ///
+    /// # Returns
+    ///
+    /// The outline's `NodeShape`, or `None` if the node has no circle outline or is not in `pose`.
+    pub fn circle_outline_shape(&self, pose: &Pose) -> Option<NodeShape> {
+        if !self.use_circle_outline || !matches!(self.node_type, NodeType::Circle | NodeType::FilledCircle) {
+            return None;
+        }
+        let transform = pose.get(self.draw_order_index)?;
+
+        let center = (transform.start + transform.end) / 2.0;
+        let half_length = transform.start.distance(transform.end) / 2.0;
+        let circle_radius = if self.node_type == NodeType::Circle || self.circle_is_hollow {
+            half_length + transform.thickness / 2.0
+        } else {
+            half_length
+        };
+
+        Some(NodeShape::ring(
+            center,
+            circle_radius + transform.thickness / 2.0,
+            circle_radius,
+            transform.angle.to_radians(),
+        ))
+    }
+
+    /// Gets the outer boundary of this node's shape. See `shape`.
+    pub fn outline(&self, pose: &Pose) -> Vec<Vec2> {
+        self.shape(pose).contours.into_iter().next().unwrap_or_default()
+    }
+
+    /// Gets the line the node follows from its start to its end, as a polyline in global coordinates.
+    ///
+    /// This is a straight line unless the node is a segment, rounded segment or trapezoid with a non-zero
+    /// `segment_curve_radius_and_default_curve_radius`. Then it is an arc of a circle of that radius, times the node's
+    /// effective scale, through the node's start and end; positive radii bulge to the left of the node and negative ones
+    /// to the right. Radii shorter than half the node's length are treated as exactly half, i.e. a half circle.
+    /// * `curve_circulization` takes the long way round the circle instead of the short way.
+    /// * `half_arc` keeps only the first half of the arc, from the start of the node to the middle of the arc.
+    pub fn centerline(&self, pose: &Pose) -> Vec<Vec2> {
+        self.centerline_with_steps(pose, None)
+    }
+
+    /// Like `centerline`, but with curves split into exactly `steps` straight pieces if `steps` is given.
+    pub(crate) fn centerline_with_steps(&self, pose: &Pose, steps: Option<usize>) -> Vec<Vec2> {
+        let Some(transform) = pose.get(self.draw_order_index) else {
+            return Vec::new();
+        };
+        let (start, end) = (transform.start, transform.end);
+
+        let is_curvable = matches!(self.node_type, NodeType::Segment | NodeType::RoundedSegment | NodeType::Trapezoid);
+        let radius = self.segment_curve_radius_and_default_curve_radius as f32 * transform.scale;
+        let chord = end - start;
+        let chord_length = chord.length();
+
+        if !is_curvable || radius == 0.0 || chord_length <= f32::EPSILON {
+            return vec![start, end];
+        }
+
+        let bulge_side = chord.perp().normalize() * radius.signum();
+        let radius = radius.abs().max(chord_length / 2.0);
+        let middle = (start + end) / 2.0;
+        let center_distance = libm::sqrtf((radius * radius - chord_length * chord_length / 4.0).max(0.0));
+        let center = if self.curve_circulization {
+            middle + bulge_side * center_distance
+        } else {
+            middle - bulge_side * center_distance
+        };
+
+        let start_angle = angle_of(start - center);
+        let mut sweep = wrap_radians(angle_of(end - center) - start_angle);
+        let sweep_middle = center + Vec2::new(libm::cosf(start_angle + sweep / 2.0), libm::sinf(start_angle + sweep / 2.0)) * radius;
+        if (sweep_middle - middle).dot(bulge_side) < 0.0 {
+            sweep -= TAU * sweep.signum();
+        }
+        if self.half_arc {
+            sweep /= 2.0;
+        }
+
+        let steps = steps.unwrap_or((libm::fabsf(sweep) / TAU * CIRCLE_SEGMENTS as f32) as usize).max(1);
+        arc_points(center, radius, start_angle, sweep, steps)
+    }
+}
+
+/// Rule deciding which parts of a self-overlapping outline are filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillRule {
+    /// A point is filled if a ray from it crosses the outline an odd number of times, so overlaps cancel out.
+    EvenOdd,
+    /// A point is filled if the outline winds around it at least once, so overlaps stay filled.
+    #[default]
+    NonZero,
+}
+
+impl FillRule {
+    fn is_inside(&self, winding: i32) -> bool {
+        match self {
+            FillRule::EvenOdd => winding % 2 != 0,
+            FillRule::NonZero => winding != 0,
+        }
+    }
+}
+
+/// Triangulates the area enclosed by closed `contours` under `fill_rule`.
+///
+/// Contours may be concave, self-intersecting and overlap each other. The area is cut into horizontal slabs at every
+/// vertex and every crossing of two edges, so within a slab no edges cross and each filled span between two edges
+/// is a trapezoid.
+pub(crate) fn triangulate(contours: &[Vec<Vec2>], fill_rule: FillRule) -> Vec<[Vec2; 3]> {
+    // Edges as (lower point, upper point, winding direction), leaving out horizontal ones, which never bound a slab.
+    let edges: Vec<(Vec2, Vec2, i32)> = contours
+        .iter()
+        .flat_map(|contour| {
+            (0..contour.len()).map(move |i| (contour[i], contour[(i + 1) % contour.len()]))
+        })
+        .filter(|(a, b)| a.y != b.y)
+        .map(|(a, b)| if a.y < b.y { (a, b, 1) } else { (b, a, -1) })
+        .collect();
+
+    let mut slab_bounds: Vec<f32> = edges.iter().flat_map(|(a, b, _)| [a.y, b.y]).collect();
+    for (i, (a, b, _)) in edges.iter().enumerate() {
+        for (c, d, _) in &edges[i + 1..] {
+            if let Some(y) = crossing_height(*a, *b, *c, *d) {
+                slab_bounds.push(y);
+            }
+        }
+    }
+    slab_bounds.sort_by(f32::total_cmp);
+    slab_bounds.dedup();
+
+    let x_at = |(a, b, _): &(Vec2, Vec2, i32), y: f32| a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y);
+
+    let mut triangles = Vec::new();
+    for bounds in slab_bounds.windows(2) {
+        let (bottom, top) = (bounds[0], bounds[1]);
+        let middle = (bottom + top) / 2.0;
+
+        let mut crossing: Vec<&(Vec2, Vec2, i32)> = edges
+            .iter()
+            .filter(|(a, b, _)| a.y <= bottom && b.y >= top)
+            .collect();
+        crossing.sort_by(|e, f| x_at(e, middle).total_cmp(&x_at(f, middle)));
+
+        let mut winding = 0;
+        for pair in crossing.windows(2) {
+            winding += pair[0].2;
+            if !fill_rule.is_inside(winding) {
+                continue;
+            }
+
+            let bottom_left = Vec2::new(x_at(pair[0], bottom), bottom);
+            let bottom_right = Vec2::new(x_at(pair[1], bottom), bottom);
+            let top_right = Vec2::new(x_at(pair[1], top), top);
+            let top_left = Vec2::new(x_at(pair[0], top), top);
+            for triangle in [[bottom_left, bottom_right, top_right], [bottom_left, top_right, top_left]] {
+                let [p, q, r] = triangle;
+                if (q - p).perp_dot(r - p).abs() > f32::EPSILON {
+                    triangles.push(triangle);
+                }
+            }
+        }
+    }
+
+    triangles
+}
+
+/// Height at which two edges properly cross, if they do.
+fn crossing_height(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> Option<f32> {
+    let ab = b - a;
+    let cd = d - c;
+    let denominator = ab.perp_dot(cd);
+    if denominator == 0.0 {
+        return None;
+    }
+
+    let t = (c - a).perp_dot(cd) / denominator;
+    let u = (c - a).perp_dot(ab) / denominator;
+    if t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0 {
+        Some(a.y + ab.y * t)
+    } else {
+        None
+    }
+}
+
+/// Direction the node points in, from its start to its end.
+fn direction(transform: &NodeTransform) -> Vec2 {
+    let angle = transform.angle.to_radians();
+    Vec2::new(libm::cosf(angle), libm::sinf(angle))
+}
+
+fn angle_of(vector: Vec2) -> f32 {
+    libm::atan2f(vector.y, vector.x)
+}
+
+/// Wraps an angle in radians into -PI..=PI.
+fn wrap_radians(angle: f32) -> f32 {
+    libm::remainderf(angle, TAU)
+}
+
+fn circle_points(center: Vec2, radius: f32, start_angle: f32) -> Vec<Vec2> {
+    arc_points(center, radius, start_angle, TAU, CIRCLE_SEGMENTS)
+        .into_iter()
+        .take(CIRCLE_SEGMENTS)
+        .collect()
+}
+
+/// Points of an arc, including both ends.
+fn arc_points(center: Vec2, radius: f32, start_angle: f32, sweep: f32, steps: usize) -> Vec<Vec2> {
+    (0..=steps)
+        .map(|i| {
+            let t = start_angle + sweep * i as f32 / steps as f32;
+            center + Vec2::new(libm::cosf(t), libm::sinf(t)) * radius
+        })
+        .collect()
+}
+
+/// Triangulates a convex polygon.
+fn fan(points: &[Vec2]) -> Vec<[Vec2; 3]> {
+    (1..points.len().saturating_sub(1))
+        .map(|i| [points[0], points[i], points[i + 1]])
+        .collect()
+}
+
+/// Thickens a polyline into a band, with `half_width(t)` giving half its width a fraction `t` of the way along it.
+fn stroke(centerline: &[Vec2], half_width: impl Fn(f32) -> f32, round_start: bool, round_end: bool) -> NodeShape {
+    if centerline.len() < 2 {
+        return NodeShape::default();
+    }
+
+    let last = centerline.len() - 1;
+    let tangent = |i: usize| {
+        let (a, b) = (centerline[i.saturating_sub(1)], centerline[(i + 1).min(last)]);
+        let tangent = (b - a).normalize_or_zero();
+        if tangent == Vec2::ZERO { Vec2::X } else { tangent }
+    };
+
+    let mut left = Vec::with_capacity(centerline.len());
+    let mut right = Vec::with_capacity(centerline.len());
+    for (i, point) in centerline.iter().enumerate() {
+        let normal = tangent(i).perp() * half_width(i as f32 / last as f32);
+        left.push(*point + normal);
+        right.push(*point - normal);
+    }
+
+    let mut triangles = Vec::with_capacity(last * 2 + CIRCLE_SEGMENTS);
+    for i in 0..last {
+        triangles.push([right[i], right[i + 1], left[i + 1]]);
+        triangles.push([right[i], left[i + 1], left[i]]);
+    }
+
+    // Half circles around the ends, from the right edge round to the left edge.
+    let cap = |point: Vec2, outwards: Vec2, radius: f32| {
+        arc_points(point, radius, angle_of(outwards) - PI / 2.0, PI, CIRCLE_SEGMENTS / 2)
+    };
+
+    let mut outline = right.clone();
+    if round_end {
+        let end_cap = cap(centerline[last], tangent(last), half_width(1.0));
+        triangles.extend(fan_around(centerline[last], &end_cap));
+        outline.extend(&end_cap[1..end_cap.len() - 1]);
+    }
+    outline.extend(left.iter().rev());
+    if round_start {
+        let start_cap = cap(centerline[0], -tangent(0), half_width(0.0));
+        triangles.extend(fan_around(centerline[0], &start_cap));
+        outline.extend(&start_cap[1..start_cap.len() - 1]);
+    }
+
+    NodeShape {
+        contours: vec![outline],
+        triangles,
+    }
+}
+
+fn fan_around(center: Vec2, points: &[Vec2]) -> Vec<[Vec2; 3]> {
+    points
+        .windows(2)
+        .map(|pair| [center, pair[0], pair[1]])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DrawOrderIndex, NodeOptions, Stickfigure, TriangleType};
+
+    fn right_triangle_tip(triangle_flipped: bool) -> Vec2 {
+        let mut stickfigure = Stickfigure::new();
+        let draw_index = stickfigure
+            .add_node(
+                Node::from_options(NodeOptions {
+                    node_type: NodeType::Triangle,
+                    length: 10.0,
+                    thickness: 4,
+                    triangle_type: TriangleType::RightTriangle,
+                    triangle_flipped,
+                    ..Default::default()
+                }),
+                DrawOrderIndex(0),
+            )
+            .unwrap();
+        let pose = stickfigure.compute_pose();
+
+        stickfigure.get_node(draw_index).unwrap().shape(&pose).contours[0][1]
+    }
+
+    #[test]
+    fn right_triangles_follow_their_right_triangle_direction() {
+        assert!(right_triangle_tip(false).distance(Vec2::new(10.0, 2.0)) < 1e-3);
+        assert!(right_triangle_tip(true).distance(Vec2::new(10.0, -2.0)) < 1e-3);
+    }
+}
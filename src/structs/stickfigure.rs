@@ -1,8 +1,4 @@
-use core::cell::RefCell;
-
 use core2::io::Cursor;
-use core2::io::Read;
-use core2::io::Seek;
 use hashbrown::HashMap;
 use petgraph::graph::NodeIndex;
 use petgraph::visit::Dfs;
@@ -12,20 +8,23 @@ use petgraph::visit::Visitable;
 use petgraph::Direction;
 use petgraph::Graph;
 extern crate alloc;
-use alloc::{format, rc::Rc, vec, vec::Vec};
+use alloc::{format, string::String, vec, vec::Vec};
 use serde::Deserialize;
 use serde::Serialize;
 
 use crate::serialization::read::read_stickfigure;
+use crate::serialization::text::read_text;
+use crate::serialization::text::write_text;
 use crate::serialization::write::write_stickfigure;
 use crate::structs::node::*;
+use crate::Capabilities;
 use crate::Color;
+use crate::ConnectorData;
+use crate::ConnectorMethod;
 use crate::LibraryError;
 use crate::Polyfill;
 use crate::StickfigureError;
 
-const NODE_LIMIT: usize = 400;
-
 #[cfg(feature = "tryreadanyway")]
 pub const SUPPORTED_APP_VERSION: i32 = 9999999;
 
@@ -39,8 +38,11 @@ pub const SUPPORTED_APP_VERSION: i32 = 423;
 #[cfg(not(feature = "tryreadanyway"))]
 pub const SUPPORTED_APP_BUILD: i32 = 72;
 
+/// Oldest Stick Nodes version whose files this library writes. Files of older versions are compressed.
+pub const MIN_WRITABLE_VERSION: i32 = 160;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(transparent)]
 pub struct DrawOrderIndex(pub i32);
 
@@ -52,20 +54,14 @@ impl Into<DrawOrderIndex> for i32 {
     }
 }
 
-pub(crate) struct NodeIndices {
-    draw_index: DrawOrderIndex,
-    node_index: NodeIndex,
-}
-
 #[derive(Debug, Clone)]
 pub struct Stickfigure {
     pub version: i32,
     pub build: i32,
     pub scale: f32,
     pub color: Color,
-    pub nodes: Graph<Rc<RefCell<Node>>, ()>,
-    pub polyfills: Vec<Rc<RefCell<Polyfill>>>,
-    polyfill_anchors: Vec<DrawOrderIndex>,
+    pub nodes: Graph<Node, ()>,
+    pub polyfills: Vec<Polyfill>,
     next_draw_index: DrawOrderIndex,
     draw_index_map: HashMap<NodeIndex, DrawOrderIndex>,
     node_index_map: HashMap<DrawOrderIndex, NodeIndex>,
@@ -73,6 +69,7 @@ pub struct Stickfigure {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SerializableStickfigure {
     pub version: i32,
     pub build: i32,
@@ -82,6 +79,17 @@ pub struct SerializableStickfigure {
     pub polyfills: Vec<Polyfill>,
 }
 
+#[cfg(feature = "schemars")]
+impl SerializableStickfigure {
+    /// Generates a JSON Schema (draft 2020-12) for `SerializableStickfigure` as serialized with serde, e.g. to JSON.
+    ///
+    /// `SerializableNode`, `Polyfill`, `ConnectorData`, `Color` and the enums they use are defined under `$defs`, so
+    /// the schema can be used to validate stickfigure JSON and to generate types for it in other languages.
+    pub fn json_schema() -> schemars::Schema {
+        schemars::schema_for!(SerializableStickfigure)
+    }
+}
+
 impl Default for Stickfigure {
     fn default() -> Self {
         Self {
@@ -94,7 +102,6 @@ impl Default for Stickfigure {
             next_draw_index: DrawOrderIndex(0),
             draw_index_map: HashMap::new(),
             node_index_map: HashMap::new(),
-            polyfill_anchors: Vec::new(),
             is_node_limit_enabled: true,
         }
     }
@@ -119,9 +126,11 @@ impl Stickfigure {
             return Err(LibraryError::UnsupportedBuild(version, build));
         }
 
-        let mut stickfigure = Stickfigure::new();
-        stickfigure.version = version;
-        stickfigure.build = build;
+        let mut stickfigure = Stickfigure {
+            version,
+            build,
+            ..Default::default()
+        };
         stickfigure.add_root_node();
 
         Ok(stickfigure)
@@ -130,15 +139,11 @@ impl Stickfigure {
     pub fn to_serializable(&self) -> SerializableStickfigure {
         let root_node =self.get_node(DrawOrderIndex(0)).expect("Possibly an internal bug, but please check if the stickfigure you're trying to serialize has its root node draw order index set as 0. If it is, this is probably a library bug. If it is not 0, then that might be on your end :p");
         let mut nodes = Vec::new();
-        let serializable_nodes = root_node.borrow().build_serializable_tree(
+        let serializable_nodes = root_node.build_serializable_tree(
             &self.nodes,
-            self.node_index_from_draw_order(root_node.borrow().draw_order_index),
+            self.node_index_from_draw_order(root_node.draw_order_index),
         );
-        let serializable_polyfills = self
-            .polyfills
-            .iter()
-            .map(|poly| poly.borrow().clone())
-            .collect();
+        let serializable_polyfills = self.polyfills.clone();
         nodes.push(serializable_nodes);
         SerializableStickfigure {
             version: self.version,
@@ -150,6 +155,153 @@ impl Stickfigure {
         }
     }
 
+    /// Creates a `Stickfigure` from a `SerializableStickfigure`, e.g. one made with `to_serializable` and read back from JSON.
+    ///
+    /// Draw order indices are kept as they are.
+    ///
+    /// # Returns
+    ///
+    /// Result of the `Stickfigure`. Fails if its version or build is not supported, if `nodes` doesn't hold exactly one
+    /// root node with draw order index 0, if two nodes share a draw order index, or if a polyfill or connector refers
+    /// to a node that does not exist.
+    pub fn from_serializable(serializable: &SerializableStickfigure) -> Result<Self, LibraryError> {
+        if serializable.version > Stickfigure::default().version {
+            return Err(LibraryError::UnsupportedVersion(serializable.version));
+        } else if serializable.version == Stickfigure::default().version
+            && serializable.build > Stickfigure::default().build
+        {
+            return Err(LibraryError::UnsupportedBuild(serializable.version, serializable.build));
+        }
+
+        let [root] = serializable.nodes.as_slice() else {
+            return Err(StickfigureError::NodeError(format!(
+                "Expected exactly one root node, but found {}.",
+                serializable.nodes.len()
+            ))
+            .into());
+        };
+        if root.draw_order_index != DrawOrderIndex(0) {
+            return Err(StickfigureError::RootNodeDrawOrder(format!(
+                "The root node has draw order index {}.",
+                root.draw_order_index.0
+            ))
+            .into());
+        }
+
+        let mut stickfigure = Stickfigure {
+            version: serializable.version,
+            build: serializable.build,
+            scale: serializable.scale,
+            color: serializable.color,
+            ..Default::default()
+        };
+        stickfigure.add_root_node();
+        if let Some(root_node) = stickfigure.get_node_mut(DrawOrderIndex(0)) {
+            *root_node = Node {
+                node_type: NodeType::RootNode,
+                ..Node::from_serializable(root)
+            };
+        }
+
+        let mut stack: Vec<(&SerializableNode, DrawOrderIndex)> =
+            root.children.iter().map(|child| (child, DrawOrderIndex(0))).collect();
+        while let Some((node, parent_draw_index)) = stack.pop() {
+            stickfigure.add_node_at_unique_index(Node::from_serializable(node), parent_draw_index, node.draw_order_index)?;
+            stack.extend(node.children.iter().map(|child| (child, node.draw_order_index)));
+        }
+
+        let referenced_indices: Vec<DrawOrderIndex> = serializable
+            .polyfills
+            .iter()
+            .flat_map(|polyfill| {
+                core::iter::once(polyfill.anchor_node_draw_index)
+                    .chain(polyfill.attached_node_draw_indices.iter().copied())
+            })
+            .chain(
+                stickfigure
+                    .nodes
+                    .node_weights()
+                    .filter_map(|node| node.connector_data.as_ref().map(|data| data.end_node_draw_index)),
+            )
+            .collect();
+        let missing_indices = stickfigure.missing_draw_indices(&referenced_indices);
+        if !missing_indices.is_empty() {
+            return Err(StickfigureError::InvalidDrawIndices(
+                format!("{:?}", missing_indices),
+                String::from("Polyfills and connectors can only refer to nodes in the stickfigure."),
+            )
+            .into());
+        }
+        stickfigure.polyfills = serializable.polyfills.clone();
+
+        Ok(stickfigure)
+    }
+
+    /// Changes the Stick Nodes version and build the stickfigure is saved for.
+    ///
+    /// # Returns
+    ///
+    /// Result of `()`. Fails, without changing anything, if the version or build is not supported by this library, or if
+    /// the stickfigure uses anything that version and build can't load (see `check_capabilities`). Versions before
+    /// `MIN_WRITABLE_VERSION` are not supported, as files of those versions are compressed and this library can't write them.
+    pub fn set_version_and_build(&mut self, version: i32, build: i32) -> Result<(), LibraryError> {
+        Stickfigure::check_writable_version(version, build)?;
+
+        let (old_version, old_build) = (self.version, self.build);
+        self.version = version;
+        self.build = build;
+        if let Err(err) = self.check_capabilities() {
+            self.version = old_version;
+            self.build = old_build;
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+
+    /// Checks that this library can write stickfigures of a version and build, i.e. that it is no newer than
+    /// `SUPPORTED_APP_VERSION` and its build, and no older than `MIN_WRITABLE_VERSION`.
+    pub(crate) fn check_writable_version(version: i32, build: i32) -> Result<(), LibraryError> {
+        if version > Stickfigure::default().version || version < MIN_WRITABLE_VERSION {
+            return Err(LibraryError::UnsupportedVersion(version));
+        } else if version == Stickfigure::default().version && build > Stickfigure::default().build {
+            return Err(LibraryError::UnsupportedBuild(version, build));
+        }
+
+        Ok(())
+    }
+
+    /// Gets what the Stick Nodes version and build of this `Stickfigure` can load.
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::for_version(self.version, self.build)
+    }
+
+    /// Checks that every node and polyfill is supported by the version and build of this `Stickfigure`,
+    /// and that the node limit is respected (if enabled).
+    ///
+    /// This is checked by `to_bytes`, so that a stickfigure that won't load in the targeted app is never written.
+    pub fn check_capabilities(&self) -> Result<(), StickfigureError> {
+        let capabilities = self.capabilities();
+
+        if self.is_node_limit_enabled {
+            capabilities.check_node_count(self.nodes.node_count(), 0)?;
+        }
+
+        if !capabilities.polyfills && !self.polyfills.is_empty() {
+            return Err(StickfigureError::UnsupportedFeature(
+                String::from("Polyfill"),
+                self.version,
+                self.build,
+            ));
+        }
+
+        for node in self.nodes.node_weights() {
+            capabilities.check_node(node)?;
+        }
+
+        Ok(())
+    }
+
     pub fn set_is_node_limit_enabled(
         &mut self,
         is_enabled: bool,
@@ -161,15 +313,14 @@ impl Stickfigure {
     }
 
     pub(crate) fn add_root_node(&mut self) {
-        let rc_node = Rc::new(RefCell::new(Node::new()));
         let draw_index = DrawOrderIndex(0);
+        let node = Node {
+            node_type: NodeType::RootNode,
+            draw_order_index: draw_index,
+            ..Node::new()
+        };
 
-        Node::update(&rc_node, |n| {
-            n.node_type = NodeType::RootNode;
-            n.draw_order_index = draw_index;
-        });
-
-        let node_index = self.nodes.add_node(Rc::clone(&rc_node));
+        let node_index = self.nodes.add_node(node);
 
         self.remap_draw_index(node_index, draw_index);
     }
@@ -183,7 +334,11 @@ impl Stickfigure {
     }
 
     /// Get raw bytes of a `.nodes` file from an existing `Stickfigure`.
+    ///
+    /// Fails if the stickfigure uses anything its version and build can't load. See `check_capabilities`.
     pub fn to_bytes(&self) -> Result<Vec<u8>, LibraryError> {
+        self.check_capabilities()?;
+
         let mut byte_vec = Vec::new();
 
         byte_vec.append(&mut write_stickfigure(self)?);
@@ -191,6 +346,40 @@ impl Stickfigure {
         Ok(byte_vec)
     }
 
+    /// Creates a new `Stickfigure` from its text form, as written by `to_text`.
+    ///
+    /// # Returns
+    ///
+    /// Result of the `Stickfigure`. Fails with `StickfigureError::InvalidText`, naming the line, if the text is malformed,
+    /// refers to a node name that isn't defined, or gives two nodes the same name or draw order index. Also fails, like
+    /// `set_version_and_build`, if the version or build is not supported or can't load the stickfigure.
+    pub fn from_text(text: &str) -> Result<Self, LibraryError> {
+        read_text(text)
+    }
+
+    /// Gets the stickfigure in a compact, human-editable text form, which `from_text` reads back into an identical stickfigure.
+    ///
+    /// ```text
+    /// stickfigure version=423 build=72
+    ///
+    /// node {
+    ///     node @n1 length=60 local_angle=90 {
+    ///         node length=30 use_segment_color color=#C81E1EFF
+    ///     }
+    ///     node connector=(end=@n1 percent=0.5)
+    /// }
+    ///
+    /// polyfill anchor=@n1 attached=[@n1] color=#FFCC00FF use_polyfill_color
+    /// ```
+    ///
+    /// Nodes are nested under their parents, and only properties that differ from those of `Node::default()` are written.
+    /// Flags that are `true` are written as just their name. Nodes that polyfills or connectors refer to are named with
+    /// `@`. Unless nodes are in draw order when read top to bottom, every node has its draw order index as `draw`.
+    /// `//` starts a comment.
+    pub fn to_text(&self) -> String {
+        write_text(self)
+    }
+
     /// Adds a new node to the stickfigure.
     ///
     /// The node is given a unique `DrawOrderIndex`.
@@ -202,22 +391,22 @@ impl Stickfigure {
     ///
     /// # Returns
     ///
-    /// Result of `DrawOrderIndex` of the newly added node.
+    /// Result of `DrawOrderIndex` of the newly added node. Fails if the node limit is reached, or if the node uses
+    /// anything the version and build of this `Stickfigure` can't load.
     pub fn add_node(
         &mut self,
         node: Node,
         parent_draw_index: DrawOrderIndex,
     ) -> Result<DrawOrderIndex, StickfigureError> {
         self.check_if_can_add_node(1)?;
+        self.capabilities().check_node(&node)?;
 
-        let rc_node = Rc::new(RefCell::new(node));
+        let mut node = node;
         let draw_index = self.get_next_draw_index();
 
-        Node::update(&rc_node, |n| {
-            n.draw_order_index = draw_index;
-        });
+        node.draw_order_index = draw_index;
 
-        let node_index = self.nodes.add_node(rc_node);
+        let node_index = self.nodes.add_node(node);
 
         self.remap_draw_index(node_index, draw_index);
 
@@ -239,6 +428,71 @@ impl Stickfigure {
         Ok(draw_index)
     }
 
+    /// Adds a new connector node, which spans from the end of its parent towards the end of another existing node.
+    ///
+    /// See `NodeTransform::from_parent_to_connector_end` for how the connector is placed along that span.
+    ///
+    /// # Parameters
+    ///
+    /// * `node` - The `Node` to add as a connector. Any `connector_data` it already has is replaced.
+    /// * `parent_draw_index` - The `DrawOrderIndex` of the node the connector starts from.
+    /// * `end_node_draw_index` - The `DrawOrderIndex` of the node the connector connects to.
+    /// * `connector_data` - How the connector is placed. Its `end_node_draw_index` is set to `end_node_draw_index`.
+    ///
+    /// # Returns
+    ///
+    /// Result of `DrawOrderIndex` of the newly added connector. Fails if either node does not exist, if the connector
+    /// would connect a node to itself, if `percent` is outside 0..=1 for `ConnectorMethod::ByPercent` or `value` is negative
+    /// for `ConnectorMethod::ByValue`, or for any reason `add_node` fails.
+    pub fn add_connector(
+        &mut self,
+        node: Node,
+        parent_draw_index: DrawOrderIndex,
+        end_node_draw_index: DrawOrderIndex,
+        connector_data: ConnectorData,
+    ) -> Result<DrawOrderIndex, StickfigureError> {
+        if !self.draw_index_exists(parent_draw_index) {
+            return Err(StickfigureError::InvalidDrawIndex(
+                parent_draw_index.0,
+                String::from("Cannot add connector to a parent node that does not exist."),
+            ));
+        }
+        if !self.draw_index_exists(end_node_draw_index) {
+            return Err(StickfigureError::InvalidDrawIndex(
+                end_node_draw_index.0,
+                String::from("Cannot connect to a node that does not exist."),
+            ));
+        }
+        if parent_draw_index == end_node_draw_index {
+            return Err(StickfigureError::InvalidConnector(String::from(
+                "A connector can't connect its parent node to itself.",
+            )));
+        }
+        match connector_data.method {
+            ConnectorMethod::ByPercent if !(0.0..=1.0).contains(&connector_data.percent) => {
+                return Err(StickfigureError::InvalidConnector(format!(
+                    "Percent must be between 0 and 1, but is {}.",
+                    connector_data.percent
+                )));
+            }
+            ConnectorMethod::ByValue if !(connector_data.value >= 0.0 && connector_data.value.is_finite()) => {
+                return Err(StickfigureError::InvalidConnector(format!(
+                    "Value must be a finite number of at least 0, but is {}.",
+                    connector_data.value
+                )));
+            }
+            _ => {}
+        }
+
+        let mut node = node;
+        node.connector_data = Some(ConnectorData {
+            end_node_draw_index,
+            ..connector_data
+        });
+
+        self.add_node(node, parent_draw_index)
+    }
+
     pub fn change_draw_index(
         &mut self,
         draw_index: DrawOrderIndex,
@@ -256,47 +510,162 @@ impl Stickfigure {
         }
 
         let node_index = self.node_index_from_draw_order(draw_index);
+        let mut order = self.node_indices_in_draw_order();
 
-        if self.node_index_map.contains_key(&new_draw_index) {
-            let mut affected_nodes: Vec<NodeIndices> = self
-                .draw_index_map
-                .iter()
-                .filter(|(_, &draw_index)| draw_index.0 >= new_draw_index.0)
-                .map(|(&node_index, &draw_index)| NodeIndices {
-                    node_index,
-                    draw_index,
-                })
-                .collect();
+        // The node is placed directly before whichever node currently occupies the new draw order index.
+        let insert_before = self.node_index_map.get(&new_draw_index).copied();
+        order.retain(|&other| other != node_index);
+        let position = insert_before
+            .and_then(|before| order.iter().position(|&other| other == before))
+            .unwrap_or(order.len());
+        order.insert(position, node_index);
 
-            affected_nodes.sort_by_key(|node_indices| node_indices.draw_index.0);
+        self.apply_draw_order(&order);
 
-            for indices in affected_nodes.iter_mut() {
-                let draw_index_ = {
-                    let node = self.nodes.node_weight(indices.node_index).expect("This node index was retrieved from the node_index_map, so it should be valid, but apparently is not - bug in library logic");
+        Ok(())
+    }
 
-                    Node::update(&node, |n| {
-                        n.draw_order_index.0 += 1;
-                    });
+    /// Moves a node one step towards the front of the draw order, swapping it with the node drawn directly after it.
+    ///
+    /// Nodes with a higher `DrawOrderIndex` are drawn later, i.e. on top of nodes with a lower one.
+    ///
+    /// # Returns
+    ///
+    /// Result of the new `DrawOrderIndex` of the node. If the node is already in front, its index is unchanged.
+    pub fn raise(&mut self, draw_index: DrawOrderIndex) -> Result<DrawOrderIndex, StickfigureError> {
+        self.check_if_can_reorder(draw_index)?;
 
-                    node.borrow().draw_order_index
-                };
+        let next_draw_index = DrawOrderIndex(draw_index.0 + 1);
+        if !self.draw_index_exists(next_draw_index) {
+            return Ok(draw_index);
+        }
 
-                self.draw_index_map.insert(indices.node_index, draw_index_);
-                self.node_index_map.insert(draw_index_, indices.node_index);
-            }
+        self.swap_draw_order(draw_index, next_draw_index)?;
+
+        Ok(next_draw_index)
+    }
+
+    /// Moves a node one step towards the back of the draw order, swapping it with the node drawn directly before it.
+    ///
+    /// The root node always stays at `DrawOrderIndex(0)`, so a node can't be lowered past `DrawOrderIndex(1)`.
+    ///
+    /// # Returns
+    ///
+    /// Result of the new `DrawOrderIndex` of the node. If the node is already at the back, its index is unchanged.
+    pub fn lower(&mut self, draw_index: DrawOrderIndex) -> Result<DrawOrderIndex, StickfigureError> {
+        self.check_if_can_reorder(draw_index)?;
+
+        let previous_draw_index = DrawOrderIndex(draw_index.0 - 1);
+        if previous_draw_index.0 < 1 {
+            return Ok(draw_index);
         }
 
-        let node = self.nodes.node_weight(node_index).expect("Earlier in this method call the draw order index was evaluated to be valid, which means its associated node index must exist, but apparently does not - bug in library logic");
-        Node::update(&node, |n| {
-            n.draw_order_index = new_draw_index;
-        });
+        self.swap_draw_order(draw_index, previous_draw_index)?;
 
-        self.remap_draw_index(node_index, new_draw_index);
-        self.compact_draw_indices();
+        Ok(previous_draw_index)
+    }
+
+    /// Moves a node to the front of the draw order, so that it is drawn on top of every other node.
+    ///
+    /// # Returns
+    ///
+    /// Result of the new `DrawOrderIndex` of the node.
+    pub fn bring_to_front(&mut self, draw_index: DrawOrderIndex) -> Result<DrawOrderIndex, StickfigureError> {
+        self.check_if_can_reorder(draw_index)?;
+
+        let node_index = self.node_index_from_draw_order(draw_index);
+        let mut order = self.node_indices_in_draw_order();
+        order.retain(|&other| other != node_index);
+        order.push(node_index);
+
+        self.apply_draw_order(&order);
+
+        Ok(self.draw_order_from_node_index(node_index))
+    }
+
+    /// Moves a node to the back of the draw order, directly above the root node.
+    ///
+    /// # Returns
+    ///
+    /// Result of the new `DrawOrderIndex` of the node.
+    pub fn send_to_back(&mut self, draw_index: DrawOrderIndex) -> Result<DrawOrderIndex, StickfigureError> {
+        self.check_if_can_reorder(draw_index)?;
+
+        let node_index = self.node_index_from_draw_order(draw_index);
+        let mut order = self.node_indices_in_draw_order();
+        order.retain(|&other| other != node_index);
+        order.insert(1.min(order.len()), node_index);
+
+        self.apply_draw_order(&order);
+
+        Ok(self.draw_order_from_node_index(node_index))
+    }
+
+    /// Swaps the draw order of two nodes. Every other node keeps its `DrawOrderIndex`.
+    pub fn swap_draw_order(
+        &mut self,
+        draw_index_a: DrawOrderIndex,
+        draw_index_b: DrawOrderIndex,
+    ) -> Result<(), StickfigureError> {
+        self.check_if_can_reorder(draw_index_a)?;
+        self.check_if_can_reorder(draw_index_b)?;
+
+        if draw_index_a == draw_index_b {
+            return Ok(());
+        }
+
+        let node_index_a = self.node_index_from_draw_order(draw_index_a);
+        let node_index_b = self.node_index_from_draw_order(draw_index_b);
+        let mut order = self.node_indices_in_draw_order();
+
+        let position_a = order.iter().position(|&other| other == node_index_a);
+        let position_b = order.iter().position(|&other| other == node_index_b);
+        if let (Some(position_a), Some(position_b)) = (position_a, position_b) {
+            order.swap(position_a, position_b);
+        }
+
+        self.apply_draw_order(&order);
 
         Ok(())
     }
 
+    /// Moves a node and all of its descendants in the draw order, keeping the subtree's relative order.
+    ///
+    /// The subtree is taken out of the draw order and reinserted as one contiguous block that starts at `new_draw_index`
+    /// (clamped so that the root node stays at `DrawOrderIndex(0)` and the block stays within the stickfigure).
+    ///
+    /// # Parameters
+    ///
+    /// * `draw_index` - The `DrawOrderIndex` of the topmost node of the subtree.
+    /// * `new_draw_index` - The `DrawOrderIndex` the first node of the subtree should end up at.
+    ///
+    /// # Returns
+    ///
+    /// Result of the new `DrawOrderIndex` of the node at `draw_index`.
+    pub fn move_subtree_draw_order(
+        &mut self,
+        draw_index: DrawOrderIndex,
+        new_draw_index: DrawOrderIndex,
+    ) -> Result<DrawOrderIndex, StickfigureError> {
+        self.check_if_can_reorder(draw_index)?;
+
+        let node_index = self.node_index_from_draw_order(draw_index);
+        let mut subtree = self.get_children_recursive(draw_index);
+        subtree.push(draw_index);
+        subtree.sort();
+        let subtree = self.node_indices_from_draw_order_indices(&subtree);
+
+        let mut order = self.node_indices_in_draw_order();
+        order.retain(|other| !subtree.contains(other));
+
+        let position = (new_draw_index.0.max(1) as usize).min(order.len());
+        order.splice(position..position, subtree);
+
+        self.apply_draw_order(&order);
+
+        Ok(self.draw_order_from_node_index(node_index))
+    }
+
     /// Gets a reference to a node.
     ///
     /// # Parameters
@@ -306,7 +675,7 @@ impl Stickfigure {
     /// # Returns
     ///
     /// An `Option` containing a reference to the node if it exists.
-    pub fn get_node(&self, draw_index: DrawOrderIndex) -> Option<&Rc<RefCell<Node>>> {
+    pub fn get_node(&self, draw_index: DrawOrderIndex) -> Option<&Node> {
         if !self.node_index_map.contains_key(&draw_index) {
             return None;
         }
@@ -314,22 +683,60 @@ impl Stickfigure {
         self.nodes.node_weight(node_index)
     }
 
-    // Gets a mutable reference to a node.
-    //
-    // # Parameters
-    //
-    // * `draw_index` - The `DrawOrderIndex` of the node.
-    //
-    // # Returns
-    //
-    // An `Option` containing a mutable reference to the node if it exists.
-    // pub fn get_node_mut(&mut self, draw_index: DrawOrderIndex) -> Option<&mut Node> {
-    //     if !self.node_index_map.contains_key(&draw_index) {
-    //         return None;
-    //     }
-    //     let node_index = self.node_index_from_draw_order(draw_index);
-    //     self.nodes.node_weight_mut(node_index)
-    // }
+    /// Gets a mutable reference to a node.
+    ///
+    /// Unlike `update_node`, edits through it aren't checked against the version and build of this `Stickfigure`
+    /// straight away. `to_bytes` still refuses to write anything they can't load (see `check_capabilities`).
+    ///
+    /// # Parameters
+    ///
+    /// * `draw_index` - The `DrawOrderIndex` of the node.
+    ///
+    /// # Returns
+    ///
+    /// An `Option` containing a mutable reference to the node if it exists.
+    pub fn get_node_mut(&mut self, draw_index: DrawOrderIndex) -> Option<&mut Node> {
+        if !self.node_index_map.contains_key(&draw_index) {
+            return None;
+        }
+        let node_index = self.node_index_from_draw_order(draw_index);
+        self.nodes.node_weight_mut(node_index)
+    }
+
+    /// Modifies a node in place through a closure.
+    ///
+    /// This is a convenience over `get_node_mut` for one-off edits, e.g.
+    /// `stickfigure.update_node(index, |node| node.is_static = true)`.
+    ///
+    /// # Parameters
+    ///
+    /// * `draw_index` - The `DrawOrderIndex` of the node.
+    /// * `f` - Closure that receives a mutable reference to the node.
+    ///
+    /// # Returns
+    ///
+    /// Result of whatever the closure returns, or an error if the draw order index does not exist.
+    /// If the updated node uses anything the version and build of this `Stickfigure` can't load,
+    /// the update is undone and an error is returned instead.
+    pub fn update_node<F, R>(&mut self, draw_index: DrawOrderIndex, f: F) -> Result<R, StickfigureError>
+    where
+        F: FnOnce(&mut Node) -> R,
+    {
+        let capabilities = self.capabilities();
+        let node = self.get_node_mut(draw_index).ok_or_else(|| {
+            StickfigureError::InvalidDrawIndex(draw_index.0, String::from("Cannot update node."))
+        })?;
+
+        let original = node.clone();
+        let result = f(node);
+
+        if let Err(err) = capabilities.check_node(node) {
+            *node = original;
+            return Err(err);
+        }
+
+        Ok(result)
+    }
 
     /// Get the `DrawOrderIndex` of the direct parent of the `Node` at the specified `DrawOrderIndex`.
     pub fn get_parent(&self, draw_index: DrawOrderIndex) -> Option<DrawOrderIndex> {
@@ -445,7 +852,7 @@ impl Stickfigure {
 
     pub fn get_nodes_with_property<F>(&self, mut predicate: F) -> Vec<DrawOrderIndex>
     where
-        F: FnMut(&Rc<RefCell<Node>>) -> bool,
+        F: FnMut(&Node) -> bool,
     {
         let node_indices: Vec<NodeIndex> = self
             .nodes
@@ -464,18 +871,73 @@ impl Stickfigure {
         draw_indices
     }
 
-    pub fn update_nodes_with_property<F>(&mut self, mut predicate: F, mut updater: F)
+    /// Applies `updater` to every node for which `predicate` returns true.
+    ///
+    /// Like `get_node_mut`, the edits are only checked against the version and build of this `Stickfigure` when it is
+    /// written.
+    pub fn update_nodes_with_property<P, U>(&mut self, mut predicate: P, mut updater: U)
     where
-        F: FnMut(&Rc<RefCell<Node>>),
+        P: FnMut(&Node) -> bool,
+        U: FnMut(&mut Node),
     {
-        for node_index in self.nodes.node_indices() {
-            if let Some(node_data) = self.nodes.node_weight(node_index) {
-                predicate(node_data);
-                updater(node_data);
+        for node in self.nodes.node_weights_mut() {
+            if predicate(node) {
+                updater(node);
             }
         }
     }
 
+    /// Moves a node, with everything below it, to another parent. Draw order indices stay the same.
+    ///
+    /// # Parameters
+    ///
+    /// * `draw_index` - The `DrawOrderIndex` of the node to move.
+    /// * `new_parent_draw_index` - The `DrawOrderIndex` of its new parent.
+    ///
+    /// # Returns
+    ///
+    /// Result of `()`, or an error if either node does not exist, the node is the root node, or the new parent is the
+    /// node itself or one of its descendants.
+    pub fn reparent(
+        &mut self,
+        draw_index: DrawOrderIndex,
+        new_parent_draw_index: DrawOrderIndex,
+    ) -> Result<(), StickfigureError> {
+        let missing = self.missing_draw_indices(&[draw_index, new_parent_draw_index]);
+        if !missing.is_empty() {
+            return Err(StickfigureError::InvalidDrawIndices(
+                format!("{:?}", missing),
+                String::from("Cannot reparent node."),
+            ));
+        }
+        if draw_index.0 == 0 {
+            return Err(StickfigureError::GenericError(String::from("The root node can't be given a parent.")));
+        }
+        if new_parent_draw_index == draw_index || self.get_children_recursive(draw_index).contains(&new_parent_draw_index) {
+            return Err(StickfigureError::GenericError(format!(
+                "Node {} can't be moved under itself or one of its descendants ({}).",
+                draw_index.0, new_parent_draw_index.0
+            )));
+        }
+
+        let node_index = self.node_index_from_draw_order(draw_index);
+        let incoming: Vec<_> = self
+            .nodes
+            .edges_directed(node_index, Direction::Incoming)
+            .map(|edge| edge.id())
+            .collect();
+        for edge in incoming {
+            self.nodes.remove_edge(edge);
+        }
+        self.add_edge(new_parent_draw_index, draw_index);
+
+        Ok(())
+    }
+
+    /// Removes a node. Its children are reattached to its parent, and draw indices are compacted afterwards.
+    ///
+    /// Polyfills anchored at the node are removed, the node is detached from every other polyfill, and connectors
+    /// that end at the node become regular nodes.
     pub fn remove_node(&mut self, draw_index: DrawOrderIndex) -> Result<(), StickfigureError> {
         if !self.node_index_map.contains_key(&draw_index) {
             return Err(StickfigureError::InvalidDrawIndex(
@@ -493,59 +955,106 @@ impl Stickfigure {
             }
         }
 
+        // petgraph fills the removed slot with the last node in the graph, so that node's index has to be remapped.
+        let last_node_index = NodeIndex::new(self.nodes.node_count() - 1);
+        let last_draw_index = self.draw_order_from_node_index(last_node_index);
+
+        self.draw_index_map.remove(&node_index);
+        self.node_index_map.remove(&draw_index);
         self.nodes.remove_node(node_index);
+
+        if last_node_index != node_index {
+            self.draw_index_map.remove(&last_node_index);
+            self.remap_draw_index(node_index, last_draw_index);
+        }
+
+        self.remove_draw_index_references(draw_index);
         self.compact_draw_indices();
 
         Ok(())
     }
 
     pub fn add_polyfill(&mut self, polyfill: Polyfill) -> DrawOrderIndex {
-        let rc_polyfill = Rc::new(RefCell::new(polyfill));
-        let draw_index = { rc_polyfill.borrow().anchor_node_draw_index };
-        self.polyfill_anchors
-            .push(rc_polyfill.borrow().anchor_node_draw_index);
-        self.polyfills.push(rc_polyfill);
+        let draw_index = polyfill.anchor_node_draw_index;
+        self.polyfills.push(polyfill);
         draw_index
     }
 
-    pub fn get_polyfill(&self, draw_index: DrawOrderIndex) -> Option<Rc<RefCell<Polyfill>>> {
-        if let Some(polyfill) = self
+    /// Gets a reference to the polyfill anchored at the specified `DrawOrderIndex`.
+    pub fn get_polyfill(&self, draw_index: DrawOrderIndex) -> Option<&Polyfill> {
+        self.polyfills
+            .iter()
+            .find(|poly| poly.anchor_node_draw_index == draw_index)
+    }
+
+    /// Gets a mutable reference to the polyfill anchored at the specified `DrawOrderIndex`.
+    pub fn get_polyfill_mut(&mut self, draw_index: DrawOrderIndex) -> Option<&mut Polyfill> {
+        self.polyfills
+            .iter_mut()
+            .find(|poly| poly.anchor_node_draw_index == draw_index)
+    }
+
+    /// Modifies a polyfill in place through a closure that also receives the `Stickfigure`.
+    ///
+    /// Most `Polyfill` setters validate draw order indices against the `Stickfigure`, which
+    /// can't be borrowed while one of its polyfills is borrowed mutably. The polyfill is taken
+    /// out of the `Stickfigure` for the duration of the closure and put back afterwards, e.g.
+    /// `stickfigure.update_polyfill(anchor, |poly, sf| poly.set_attached_node_draw_indices(indices, sf))`.
+    ///
+    /// # Parameters
+    ///
+    /// * `anchor_draw_index` - The `DrawOrderIndex` of the anchor node of the polyfill.
+    /// * `f` - Closure that receives a mutable reference to the polyfill and a reference to the `Stickfigure`.
+    ///
+    /// # Returns
+    ///
+    /// Result of whatever the closure returns, or an error if no polyfill is anchored at the draw order index.
+    pub fn update_polyfill<F, R>(
+        &mut self,
+        anchor_draw_index: DrawOrderIndex,
+        f: F,
+    ) -> Result<R, StickfigureError>
+    where
+        F: FnOnce(&mut Polyfill, &Stickfigure) -> R,
+    {
+        let position = self
             .polyfills
             .iter()
-            .find(|poly| poly.borrow().anchor_node_draw_index == draw_index)
-        {
-            Some(Rc::clone(polyfill))
-        } else {
-            None
-        }
+            .position(|poly| poly.anchor_node_draw_index == anchor_draw_index)
+            .ok_or_else(|| {
+                StickfigureError::InvalidDrawIndex(
+                    anchor_draw_index.0,
+                    String::from("No polyfill is anchored at this draw order index. Cannot update polyfill."),
+                )
+            })?;
+
+        let mut polyfill = self.polyfills.remove(position);
+        let result = f(&mut polyfill, self);
+        self.polyfills.insert(position, polyfill);
+
+        Ok(result)
     }
 
     pub fn remove_polyfill(
         &mut self,
         anchor_draw_order: DrawOrderIndex,
     ) -> Result<(), StickfigureError> {
-        let rc_polyfills: Vec<Rc<RefCell<Polyfill>>> = self
+        match self
             .polyfills
             .iter()
-            .map(|rc_poly| Rc::clone(rc_poly))
-            .filter(|polyfill| polyfill.borrow().anchor_node_draw_index != anchor_draw_order)
-            .collect();
-
-        let anchor_to_remove_index = self
-            .polyfill_anchors
-            .iter()
-            .position(|el| *el == anchor_draw_order);
-
-        match anchor_to_remove_index {
+            .position(|poly| poly.anchor_node_draw_index == anchor_draw_order)
+        {
             Some(index) => {
-                self.polyfill_anchors.remove(index);
-            },
+                self.polyfills.remove(index);
+            }
             None => {
-                return Err(StickfigureError::InvalidDrawIndex(anchor_draw_order.0, format!("Anchor node draw index not found in internal polyfill_anchors Vec. Likely some kind of logical error. Cancelling polyfill removal.")))
-            },
+                return Err(StickfigureError::InvalidDrawIndex(
+                    anchor_draw_order.0,
+                    String::from("No polyfill is anchored at this draw order index. Cancelling polyfill removal."),
+                ))
+            }
         }
 
-        self.polyfills = rc_polyfills;
         Ok(())
     }
 
@@ -562,7 +1071,9 @@ impl Stickfigure {
     }
 
     pub fn draw_index_is_polyfill_anchor(&self, draw_index: DrawOrderIndex) -> bool {
-        self.polyfill_anchors.contains(&draw_index)
+        self.polyfills
+            .iter()
+            .any(|poly| poly.anchor_node_draw_index == draw_index)
     }
 
     /// Returns a Vec of DrawOrderIndices that do not exist in node_index_map.
@@ -575,6 +1086,12 @@ impl Stickfigure {
     }
 }
 
+// `Stickfigure` owns its nodes and polyfills by value, so it can be moved to and shared between threads.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Stickfigure>();
+};
+
 /// Private Methods
 impl Stickfigure {
     pub(crate) fn add_node_at_unique_index(
@@ -582,27 +1099,21 @@ impl Stickfigure {
         node: Node,
         parent_draw_index: DrawOrderIndex,
         draw_index: DrawOrderIndex,
-    ) -> Result<NodeIndices, StickfigureError> {
+    ) -> Result<DrawOrderIndex, StickfigureError> {
         if self.node_index_map.contains_key(&draw_index) {
             return Err(StickfigureError::OccupiedDrawIndex(draw_index.0 ,format!("add_node_at_unique_index will not attempt to shift indices. This is likely being called while reading a stickfigure file. If that's the case, the file likely has duplicate draw order indices. If that's not the case or the file has all unique draw order indices, then there's a bug with the library.")).into());
         }
 
-        let rc_node = Rc::new(RefCell::new(node));
+        let mut node = node;
+        node.draw_order_index = draw_index;
 
-        Node::update(&rc_node, |n| {
-            n.draw_order_index = draw_index;
-        });
-
-        let node_index = self.nodes.add_node(rc_node);
+        let node_index = self.nodes.add_node(node);
 
         self.remap_draw_index(node_index, draw_index);
 
         self.add_edge(parent_draw_index, draw_index);
 
-        Ok(NodeIndices {
-            draw_index,
-            node_index,
-        })
+        Ok(draw_index)
     }
 
     pub(crate) fn add_edge(
@@ -652,19 +1163,6 @@ impl Stickfigure {
             .collect()
     }
 
-    fn insert_new_indices(&mut self, node_indices: NodeIndices) {
-        if self.draw_index_map.contains_key(&node_indices.node_index)
-            || self.node_index_map.contains_key(&node_indices.draw_index)
-        {
-            panic!("Library is attempting to insert a new index pair that already exists - this is a bug with the library")
-        }
-
-        self.draw_index_map
-            .insert(node_indices.node_index, node_indices.draw_index);
-        self.node_index_map
-            .insert(node_indices.draw_index, node_indices.node_index);
-    }
-
     fn get_next_draw_index(&mut self) -> DrawOrderIndex {
         while self.node_index_map.contains_key(&self.next_draw_index) {
             self.next_draw_index.0 += 1;
@@ -678,8 +1176,21 @@ impl Stickfigure {
         self.node_index_map.insert(draw_index, node_index);
     }
 
-    fn compact_draw_indices(&mut self) {
-        // Get all current mappings, sorted by current draw index
+    /// Gives the nodes contiguous draw indices in `order`, which must list every draw order index once.
+    pub(crate) fn set_draw_order(&mut self, order: &[DrawOrderIndex]) {
+        let order = self.node_indices_from_draw_order_indices(order);
+
+        self.apply_draw_order(&order);
+    }
+
+    pub(crate) fn compact_draw_indices(&mut self) {
+        let order = self.node_indices_in_draw_order();
+
+        self.apply_draw_order(&order);
+    }
+
+    /// Returns every `NodeIndex`, sorted by current draw order index.
+    fn node_indices_in_draw_order(&self) -> Vec<NodeIndex> {
         let mut indexed_nodes: Vec<(DrawOrderIndex, NodeIndex)> = self
             .node_index_map
             .iter()
@@ -688,34 +1199,214 @@ impl Stickfigure {
 
         indexed_nodes.sort_by_key(|(draw_index, _)| *draw_index);
 
-        // Clear existing maps
+        indexed_nodes.into_iter().map(|(_, node_index)| node_index).collect()
+    }
+
+    /// Reassigns contiguous draw indices starting from 0 in the given order, and updates every polyfill and
+    /// connector that refers to a node by its draw order index.
+    fn apply_draw_order(&mut self, order: &[NodeIndex]) {
+        let mut remapped = HashMap::new();
+
+        for (new_draw_index, node_index) in order.iter().enumerate() {
+            let new_draw_index = DrawOrderIndex(new_draw_index as i32);
+            if let Some(old_draw_index) = self.draw_index_map.get(node_index) {
+                remapped.insert(*old_draw_index, new_draw_index);
+            }
+        }
+
         self.draw_index_map.clear();
         self.node_index_map.clear();
 
-        // Reassign contiguous draw indices starting from 0
-        for (new_draw_index, (_, node_index)) in indexed_nodes.into_iter().enumerate() {
+        for (new_draw_index, node_index) in order.iter().enumerate() {
             let new_draw_index = DrawOrderIndex(new_draw_index as i32);
-            self.remap_draw_index(node_index, new_draw_index);
+            if let Some(node) = self.nodes.node_weight_mut(*node_index) {
+                node.draw_order_index = new_draw_index;
+            }
+            self.remap_draw_index(*node_index, new_draw_index);
         }
 
         // Update next_draw_index to be one past the highest used
         self.next_draw_index = DrawOrderIndex(self.draw_index_map.len() as i32);
+
+        self.remap_draw_index_references(&remapped);
+    }
+
+    fn remap_draw_index_references(&mut self, remapped: &HashMap<DrawOrderIndex, DrawOrderIndex>) {
+        let remap = |draw_index: &mut DrawOrderIndex| {
+            if let Some(new_draw_index) = remapped.get(draw_index) {
+                *draw_index = *new_draw_index;
+            }
+        };
+
+        for polyfill in self.polyfills.iter_mut() {
+            remap(&mut polyfill.anchor_node_draw_index);
+            polyfill.attached_node_draw_indices.iter_mut().for_each(remap);
+        }
+
+        for node in self.nodes.node_weights_mut() {
+            if let Some(connector_data) = node.connector_data.as_mut() {
+                remap(&mut connector_data.end_node_draw_index);
+            }
+        }
+    }
+
+    /// Drops every polyfill and connector reference to a node that is being removed, so that
+    /// compacting draw indices afterwards can't make them point at a different node.
+    fn remove_draw_index_references(&mut self, draw_index: DrawOrderIndex) {
+        self.polyfills
+            .retain(|polyfill| polyfill.anchor_node_draw_index != draw_index);
+
+        for polyfill in self.polyfills.iter_mut() {
+            polyfill
+                .attached_node_draw_indices
+                .retain(|attached| *attached != draw_index);
+        }
+
+        for node in self.nodes.node_weights_mut() {
+            if node
+                .connector_data
+                .as_ref()
+                .is_some_and(|connector_data| connector_data.end_node_draw_index == draw_index)
+            {
+                node.connector_data = None;
+            }
+        }
+    }
+
+    fn check_if_can_reorder(&self, draw_index: DrawOrderIndex) -> Result<(), StickfigureError> {
+        if !self.draw_index_exists(draw_index) {
+            return Err(StickfigureError::InvalidDrawIndex(
+                draw_index.0,
+                String::from("Not attempting to change any indices."),
+            ));
+        }
+
+        if draw_index.0 == 0 {
+            return Err(StickfigureError::RootNodeDrawOrder(String::from(
+                "Not attempting to change any indices.",
+            )));
+        }
+
+        Ok(())
     }
 
     fn check_if_can_add_node(
         &self,
         number_of_nodes_being_added: usize,
     ) -> Result<(), StickfigureError> {
-        let node_count = self.nodes.node_count();
         if self.is_node_limit_enabled {
-            if node_count >= NODE_LIMIT {
-                return Err(StickfigureError::NodeLimitError(
-                    number_of_nodes_being_added,
-                    node_count,
-                    NODE_LIMIT,
-                ));
-            }
+            self.capabilities()
+                .check_node_count(self.nodes.node_count(), number_of_nodes_being_added)?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::segment;
+
+    #[test]
+    fn set_version_and_build_rejects_versions_it_cannot_write() {
+        let mut stickfigure = Stickfigure::new();
+
+        assert!(matches!(
+            stickfigure.set_version_and_build(MIN_WRITABLE_VERSION - 1, 1),
+            Err(LibraryError::UnsupportedVersion(_))
+        ));
+        assert_eq!(stickfigure.version, SUPPORTED_APP_VERSION);
+
+        stickfigure.set_version_and_build(MIN_WRITABLE_VERSION, 1).unwrap();
+        let read_back = Stickfigure::from_bytes(stickfigure.to_bytes().unwrap()).unwrap();
+        assert_eq!(read_back.version, MIN_WRITABLE_VERSION);
+    }
+
+    #[test]
+    fn reparent_moves_a_subtree_and_refuses_cycles() {
+        let mut stickfigure = Stickfigure::new();
+        let arm = stickfigure.add_node(segment(10.0, 0.0), DrawOrderIndex(0)).unwrap();
+        let hand = stickfigure.add_node(segment(10.0, 0.0), arm).unwrap();
+        let leg = stickfigure.add_node(segment(10.0, 90.0), DrawOrderIndex(0)).unwrap();
+
+        assert!(stickfigure.reparent(arm, hand).is_err());
+        assert!(stickfigure.reparent(arm, arm).is_err());
+        assert!(stickfigure.reparent(DrawOrderIndex(0), leg).is_err());
+        assert_eq!(stickfigure.get_parent(arm), Some(DrawOrderIndex(0)));
+
+        stickfigure.reparent(arm, leg).unwrap();
+
+        assert_eq!(stickfigure.get_parent(arm), Some(leg));
+        assert_eq!(stickfigure.get_parent(hand), Some(arm));
+        assert_eq!(stickfigure.get_children(DrawOrderIndex(0)), vec![leg]);
+    }
+
+    /// Lengths of the nodes a polyfill and a connector refer to, which tell the nodes apart: the polyfill's anchor and
+    /// attached nodes, then the connector's end node.
+    fn referenced_lengths(stickfigure: &Stickfigure) -> Vec<f32> {
+        let length = |draw_index| stickfigure.get_node(draw_index).unwrap().length;
+        let polyfill = &stickfigure.polyfills[0];
+        let connector_end = stickfigure
+            .get_all_node_indices()
+            .into_iter()
+            .find_map(|draw_index| stickfigure.get_node(draw_index).unwrap().connector_data.clone())
+            .unwrap()
+            .end_node_draw_index;
+
+        let mut lengths = vec![length(polyfill.anchor_node_draw_index)];
+        lengths.extend(polyfill.attached_node_draw_indices.iter().map(|draw_index| length(*draw_index)));
+        lengths.push(length(connector_end));
+        lengths
+    }
+
+    #[test]
+    fn reordering_keeps_polyfill_and_connector_references_on_the_same_nodes() {
+        let mut stickfigure = Stickfigure::new();
+        let arm = stickfigure.add_node(segment(10.0, 0.0), DrawOrderIndex(0)).unwrap();
+        let hand = stickfigure.add_node(segment(20.0, 0.0), arm).unwrap();
+        let leg = stickfigure.add_node(segment(30.0, 90.0), DrawOrderIndex(0)).unwrap();
+        let foot = stickfigure.add_node(segment(40.0, 0.0), leg).unwrap();
+        stickfigure
+            .add_connector(segment(50.0, 0.0), hand, foot, ConnectorData::default())
+            .unwrap();
+        stickfigure.add_polyfill(Polyfill {
+            anchor_node_draw_index: arm,
+            attached_node_draw_indices: vec![arm, hand, leg, foot],
+            ..Default::default()
+        });
+        let expected = referenced_lengths(&stickfigure);
+        let length = |stickfigure: &Stickfigure, draw_index| stickfigure.get_node(draw_index).unwrap().length;
+
+        let raised = stickfigure.raise(arm).unwrap();
+        assert_eq!(length(&stickfigure, raised), 10.0);
+        assert_eq!(referenced_lengths(&stickfigure), expected);
+
+        let lowered = stickfigure.lower(foot).unwrap();
+        assert_eq!(length(&stickfigure, lowered), 40.0);
+        assert_eq!(referenced_lengths(&stickfigure), expected);
+
+        let front = stickfigure.bring_to_front(DrawOrderIndex(1)).unwrap();
+        assert_eq!(front, DrawOrderIndex(5));
+        assert_eq!(referenced_lengths(&stickfigure), expected);
+
+        let back = stickfigure.send_to_back(DrawOrderIndex(5)).unwrap();
+        assert_eq!(back, DrawOrderIndex(1));
+        assert_eq!(referenced_lengths(&stickfigure), expected);
+
+        stickfigure.swap_draw_order(DrawOrderIndex(1), DrawOrderIndex(4)).unwrap();
+        assert_eq!(referenced_lengths(&stickfigure), expected);
+
+        let leg = stickfigure.get_nodes_with_property(|node| node.length == 30.0)[0];
+        let moved = stickfigure.move_subtree_draw_order(leg, DrawOrderIndex(1)).unwrap();
+        assert_eq!(moved, DrawOrderIndex(1));
+        assert_eq!(length(&stickfigure, DrawOrderIndex(2)), 40.0);
+        assert_eq!(referenced_lengths(&stickfigure), expected);
+
+        stickfigure.change_draw_index(DrawOrderIndex(5), DrawOrderIndex(1)).unwrap();
+        assert_eq!(length(&stickfigure, DrawOrderIndex(1)), 50.0);
+        assert_eq!(referenced_lengths(&stickfigure), expected);
+
+        assert!(stickfigure.raise(DrawOrderIndex(0)).is_err());
+        assert!(stickfigure.bring_to_front(DrawOrderIndex(9)).is_err());
+    }
+}
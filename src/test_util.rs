@@ -0,0 +1,12 @@
+//! Helpers shared by the unit tests.
+
+use crate::{Node, NodeOptions};
+
+/// A plain node with the given length and local angle, and every other option left at its default.
+pub(crate) fn segment(length: f32, local_angle: f32) -> Node {
+    Node::from_options(NodeOptions {
+        length,
+        local_angle,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,682 @@
+//! JavaScript bindings, built with `wasm-bindgen`.
+//!
+//! Values crossing into JavaScript are plain objects shaped like the serde form of the matching Rust types, with
+//! TypeScript declarations below, which the tests check against the serde form. Draw order indices are plain numbers.
+
+extern crate alloc;
+use alloc::{string::String, string::ToString, vec::Vec};
+
+use glam::Vec2;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::{
+    Color, ConnectorData, DrawOrderIndex, FillRule, Node, NodeOptions, NodeType, Polyfill, PolyfillOptions,
+    SerializableStickfigure, Stickfigure, SvgOptions,
+};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT_CUSTOM_SECTION: &'static str = TYPESCRIPT_TYPES;
+
+/// Only the `wasm-bindgen` output for wasm32 takes the declarations; the tests check them against the serde form.
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+const TYPESCRIPT_TYPES: &str = r#"
+export type NodeType = "RootNode" | "RoundedSegment" | "Segment" | "Circle" | "Triangle" | "FilledCircle" | "Ellipse" | "Trapezoid" | "Polygon";
+export type GradientMode = "Sideways" | "Normal";
+export type AngleLockMode = "None" | "Absolute" | "Relative";
+export type TriangleType = "Isosceles" | "RightTriangle";
+export type ConnectorMethod = "ByPercent" | "ByValue";
+export type Point = [number, number];
+
+export interface Color {
+    red: number;
+    green: number;
+    blue: number;
+    alpha: number;
+}
+
+export interface ConnectorData {
+    local_x: number;
+    local_y: number;
+    percent: number;
+    percent_default: number;
+    value: number;
+    method: ConnectorMethod;
+    reversed: boolean;
+    smart_stretch_ancestral_value: number;
+    end_node_draw_index: number;
+}
+
+export interface NodeOptions {
+    node_type: NodeType;
+    is_static: boolean;
+    is_stretchy: boolean;
+    is_floaty: boolean;
+    is_smart_stretch: boolean;
+    do_not_apply_smart_stretch: boolean;
+    smart_stretch_reset_impulse: boolean;
+    use_segment_color: boolean;
+    use_circle_outline: boolean;
+    circle_is_hollow: boolean;
+    use_gradient: boolean;
+    reverse_gradient: boolean;
+    gradient_mode: GradientMode;
+    use_segment_scale: boolean;
+    scale: number;
+    default_length: number;
+    length: number;
+    default_thickness: number;
+    thickness?: number;
+    segment_curve_radius_and_default_curve_radius: number;
+    curve_circulization: boolean;
+    segment_curve_polyfill_precision: number;
+    half_arc: boolean;
+    triangle_type: TriangleType;
+    triangle_flipped: boolean;
+    triangle_upside_down: boolean;
+    trapezoid_thickness_start: number;
+    trapezoid_thickness_end: number;
+    use_trapezoid_thickness_start: boolean;
+    use_trapezoid_thickness_end: boolean;
+    trapezoid_is_rounded_start: boolean;
+    trapezoid_is_rounded_end: boolean;
+    num_polygon_vertices: number;
+    default_local_angle: number;
+    local_angle: number;
+    default_angle: number;
+    color: Color;
+    gradient_color: Color;
+    circle_outline_color: Color;
+    angle_lock_mode: AngleLockMode;
+    angle_lock_relative_multiplier: number;
+    is_drag_locked: boolean;
+    drag_lock_angle: number;
+    smart_stretch_multiplier: number;
+    connector_data?: ConnectorData;
+}
+
+export interface SerializableNode extends Omit<NodeOptions, "connector_data"> {
+    draw_order_index: number;
+    thickness: number;
+    trapezoid_top_thickness_ratio?: number;
+    connector_data?: ConnectorData;
+    children: SerializableNode[];
+}
+
+export interface Polyfill {
+    anchor_node_draw_index: number;
+    color: Color;
+    use_polyfill_color: boolean;
+    attached_node_draw_indices: number[];
+}
+
+export interface SerializableStickfigure {
+    version: number;
+    build: number;
+    scale: number;
+    color: Color;
+    nodes: SerializableNode[];
+    polyfills: Polyfill[];
+}
+
+export interface SvgOptions {
+    padding?: number;
+    background?: Color;
+    width?: number;
+    polyfill_fill_rule?: "EvenOdd" | "NonZero";
+}
+
+export interface NodeTransform {
+    draw_index: number;
+    start: Point;
+    end: Point;
+    angle: number;
+    scale: number;
+    thickness: number;
+}
+
+export interface NodeShape {
+    contours: Point[][];
+    triangles: [Point, Point, Point][];
+}
+
+export interface PolyfillMesh {
+    triangles: [Point, Point, Point][];
+    color: Color;
+}
+
+export interface Bounds {
+    min: Point;
+    max: Point;
+}
+
+export interface Diagnostic {
+    severity: "Info" | "Warning" | "Error";
+    draw_index?: number;
+    message: string;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Color")]
+    pub type JsColor;
+    #[wasm_bindgen(typescript_type = "NodeOptions")]
+    pub type JsNodeOptions;
+    #[wasm_bindgen(typescript_type = "ConnectorData")]
+    pub type JsConnectorData;
+    #[wasm_bindgen(typescript_type = "Polyfill")]
+    pub type JsPolyfill;
+    #[wasm_bindgen(typescript_type = "Polyfill[]")]
+    pub type JsPolyfillArray;
+    #[wasm_bindgen(typescript_type = "SerializableStickfigure")]
+    pub type JsSerializableStickfigure;
+    #[wasm_bindgen(typescript_type = "SvgOptions")]
+    pub type JsSvgOptions;
+    #[wasm_bindgen(typescript_type = "NodeTransform[]")]
+    pub type JsNodeTransformArray;
+    #[wasm_bindgen(typescript_type = "NodeShape")]
+    pub type JsNodeShape;
+    #[wasm_bindgen(typescript_type = "PolyfillMesh")]
+    pub type JsPolyfillMesh;
+    #[wasm_bindgen(typescript_type = "Bounds")]
+    pub type JsBounds;
+    #[wasm_bindgen(typescript_type = "Diagnostic[]")]
+    pub type JsDiagnosticArray;
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SvgOptionsValue {
+    padding: Option<f32>,
+    background: Option<Color>,
+    width: Option<f32>,
+    polyfill_fill_rule: Option<String>,
+}
+
+#[derive(Serialize)]
+struct NodeTransformValue {
+    draw_index: i32,
+    start: [f32; 2],
+    end: [f32; 2],
+    angle: f32,
+    scale: f32,
+    thickness: f32,
+}
+
+#[derive(Serialize)]
+struct NodeShapeValue {
+    contours: Vec<Vec<[f32; 2]>>,
+    triangles: Vec<[[f32; 2]; 3]>,
+}
+
+#[derive(Serialize)]
+struct PolyfillMeshValue {
+    triangles: Vec<[[f32; 2]; 3]>,
+    color: Color,
+}
+
+#[derive(Serialize)]
+struct BoundsValue {
+    min: [f32; 2],
+    max: [f32; 2],
+}
+
+#[derive(Serialize)]
+struct DiagnosticValue {
+    severity: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    draw_index: Option<i32>,
+    message: String,
+}
+
+fn js_error(err: impl core::fmt::Display) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+fn to_js<T: Serialize, J: JsCast>(value: &T) -> Result<J, JsError> {
+    serde_wasm_bindgen::to_value(value).map(JsCast::unchecked_into).map_err(js_error)
+}
+
+fn from_js<T: DeserializeOwned>(value: impl Into<JsValue>) -> Result<T, JsError> {
+    serde_wasm_bindgen::from_value(value.into()).map_err(js_error)
+}
+
+fn point(point: Vec2) -> [f32; 2] {
+    [point.x, point.y]
+}
+
+fn triangle(triangle: &[Vec2; 3]) -> [[f32; 2]; 3] {
+    triangle.map(point)
+}
+
+/// A stickfigure, for use from JavaScript.
+#[wasm_bindgen(js_name = Stickfigure)]
+pub struct WasmStickfigure {
+    stickfigure: Stickfigure,
+}
+
+#[wasm_bindgen(js_class = Stickfigure)]
+impl WasmStickfigure {
+    /// Creates a new stickfigure with just a root node, set to the latest supported version and build.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self {
+            stickfigure: Stickfigure::new(),
+        }
+    }
+
+    /// Reads a stickfigure from the bytes of a `.nodes` file.
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<WasmStickfigure, JsError> {
+        Stickfigure::from_bytes(bytes)
+            .map(|stickfigure| Self { stickfigure })
+            .map_err(js_error)
+    }
+
+    /// Gets the bytes of a `.nodes` file.
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, JsError> {
+        self.stickfigure.to_bytes().map_err(js_error)
+    }
+
+    /// Reads a stickfigure from the object `toJson` gives, e.g. after a round trip through `JSON.stringify`.
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: JsSerializableStickfigure) -> Result<WasmStickfigure, JsError> {
+        let serializable: SerializableStickfigure = from_js(json)?;
+        Stickfigure::from_serializable(&serializable)
+            .map(|stickfigure| Self { stickfigure })
+            .map_err(js_error)
+    }
+
+    /// Gets the stickfigure as a plain object, with nodes nested under their parents.
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<JsSerializableStickfigure, JsError> {
+        to_js(&self.stickfigure.to_serializable())
+    }
+
+    /// Reads a stickfigure from the text form `toText` gives.
+    #[wasm_bindgen(js_name = fromText)]
+    pub fn from_text(text: &str) -> Result<WasmStickfigure, JsError> {
+        Stickfigure::from_text(text)
+            .map(|stickfigure| Self { stickfigure })
+            .map_err(js_error)
+    }
+
+    /// Gets the stickfigure in its compact, human-editable text form.
+    #[wasm_bindgen(js_name = toText)]
+    pub fn to_text(&self) -> String {
+        self.stickfigure.to_text()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn version(&self) -> i32 {
+        self.stickfigure.version
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn build(&self) -> i32 {
+        self.stickfigure.build
+    }
+
+    /// Changes the version and build the stickfigure is saved for. Fails if the stickfigure uses anything they can't load.
+    #[wasm_bindgen(js_name = setVersionAndBuild)]
+    pub fn set_version_and_build(&mut self, version: i32, build: i32) -> Result<(), JsError> {
+        self.stickfigure.set_version_and_build(version, build).map_err(js_error)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn scale(&self) -> f32 {
+        self.stickfigure.scale
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_scale(&mut self, scale: f32) {
+        self.stickfigure.scale = scale;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn color(&self) -> Result<JsColor, JsError> {
+        to_js(&self.stickfigure.color)
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_color(&mut self, color: JsColor) -> Result<(), JsError> {
+        self.stickfigure.color = from_js(color)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter, js_name = nodeCount)]
+    pub fn node_count(&self) -> usize {
+        self.stickfigure.nodes.node_count()
+    }
+
+    /// Gets the draw order index of every node, in draw order.
+    #[wasm_bindgen(js_name = drawIndices)]
+    pub fn draw_indices(&self) -> Vec<i32> {
+        let mut draw_indices: Vec<i32> = self
+            .stickfigure
+            .get_all_node_indices()
+            .into_iter()
+            .map(|draw_index| draw_index.0)
+            .collect();
+        draw_indices.sort();
+        draw_indices
+    }
+
+    /// Gets the properties of a node, or `undefined` if there is no node at `drawIndex`.
+    #[wasm_bindgen(js_name = getNode)]
+    pub fn get_node(&self, draw_index: i32) -> Result<Option<JsNodeOptions>, JsError> {
+        self.stickfigure
+            .get_node(DrawOrderIndex(draw_index))
+            .map(|node| to_js(&node.to_options()))
+            .transpose()
+    }
+
+    #[wasm_bindgen(js_name = getParent)]
+    pub fn get_parent(&self, draw_index: i32) -> Option<i32> {
+        self.stickfigure.get_parent(DrawOrderIndex(draw_index)).map(|parent| parent.0)
+    }
+
+    #[wasm_bindgen(js_name = getChildren)]
+    pub fn get_children(&self, draw_index: i32) -> Vec<i32> {
+        self.stickfigure
+            .get_children(DrawOrderIndex(draw_index))
+            .into_iter()
+            .map(|child| child.0)
+            .collect()
+    }
+
+    /// Gets the properties of a node made with the defaults of `NodeOptions`, to spread new nodes' properties over.
+    #[wasm_bindgen(js_name = defaultNodeOptions)]
+    pub fn default_node_options() -> Result<JsNodeOptions, JsError> {
+        to_js(&NodeOptions::default())
+    }
+
+    /// Adds a node as a child of `parent`, returning its draw order index.
+    #[wasm_bindgen(js_name = addNode)]
+    pub fn add_node(&mut self, options: JsNodeOptions, parent: i32) -> Result<i32, JsError> {
+        let node = Node::from_options(from_js(options)?);
+        self.stickfigure
+            .add_node(node, DrawOrderIndex(parent))
+            .map(|draw_index| draw_index.0)
+            .map_err(js_error)
+    }
+
+    /// Adds a connector node that spans from the end of `parent` towards the end of `endNode`, returning its draw order index.
+    #[wasm_bindgen(js_name = addConnector)]
+    pub fn add_connector(
+        &mut self,
+        options: JsNodeOptions,
+        parent: i32,
+        end_node: i32,
+        connector_data: JsConnectorData,
+    ) -> Result<i32, JsError> {
+        let node = Node::from_options(from_js(options)?);
+        let connector_data: ConnectorData = from_js(connector_data)?;
+        self.stickfigure
+            .add_connector(node, DrawOrderIndex(parent), DrawOrderIndex(end_node), connector_data)
+            .map(|draw_index| draw_index.0)
+            .map_err(js_error)
+    }
+
+    /// Replaces the properties of a node. Its place in the stickfigure, and the root node's type, stay the same.
+    #[wasm_bindgen(js_name = updateNode)]
+    pub fn update_node(&mut self, draw_index: i32, options: JsNodeOptions) -> Result<(), JsError> {
+        let updated = Node::from_options(from_js(options)?);
+        self.stickfigure
+            .update_node(DrawOrderIndex(draw_index), |node| {
+                let node_type = if node.node_type == NodeType::RootNode {
+                    NodeType::RootNode
+                } else {
+                    updated.node_type.clone()
+                };
+                *node = Node {
+                    node_type,
+                    draw_order_index: node.draw_order_index,
+                    ..updated
+                };
+            })
+            .map_err(js_error)
+    }
+
+    /// Removes a node. Its children are moved to its parent, and draw order indices above it shift down by one.
+    #[wasm_bindgen(js_name = removeNode)]
+    pub fn remove_node(&mut self, draw_index: i32) -> Result<(), JsError> {
+        self.stickfigure.remove_node(DrawOrderIndex(draw_index)).map_err(js_error)
+    }
+
+    /// Moves a node to another draw order index, shifting the nodes in between.
+    #[wasm_bindgen(js_name = changeDrawIndex)]
+    pub fn change_draw_index(&mut self, draw_index: i32, new_draw_index: i32) -> Result<(), JsError> {
+        self.stickfigure
+            .change_draw_index(DrawOrderIndex(draw_index), DrawOrderIndex(new_draw_index))
+            .map_err(js_error)
+    }
+
+    pub fn polyfills(&self) -> Result<JsPolyfillArray, JsError> {
+        to_js(&self.stickfigure.polyfills)
+    }
+
+    /// Gets the polyfill anchored to a node, or `undefined` if there is none.
+    #[wasm_bindgen(js_name = getPolyfill)]
+    pub fn get_polyfill(&self, anchor: i32) -> Result<Option<JsPolyfill>, JsError> {
+        self.stickfigure
+            .get_polyfill(DrawOrderIndex(anchor))
+            .map(to_js)
+            .transpose()
+    }
+
+    /// Adds a polyfill, checking that its nodes exist and its anchor node isn't anchoring another polyfill.
+    #[wasm_bindgen(js_name = addPolyfill)]
+    pub fn add_polyfill(&mut self, polyfill: JsPolyfill) -> Result<(), JsError> {
+        let options: PolyfillOptions = from_js(polyfill)?;
+        let polyfill = Polyfill::from_options(options, &self.stickfigure).map_err(js_error)?;
+        self.stickfigure.add_polyfill(polyfill);
+        Ok(())
+    }
+
+    /// Replaces the color and attached nodes of the polyfill with the same anchor node as `polyfill`.
+    #[wasm_bindgen(js_name = updatePolyfill)]
+    pub fn update_polyfill(&mut self, polyfill: JsPolyfill) -> Result<(), JsError> {
+        let updated: Polyfill = from_js(polyfill)?;
+        self.stickfigure
+            .update_polyfill(updated.anchor_node_draw_index, |polyfill, stickfigure| {
+                polyfill.color = updated.color;
+                polyfill.use_polyfill_color = updated.use_polyfill_color;
+                polyfill.set_attached_node_draw_indices(updated.attached_node_draw_indices, stickfigure)
+            })
+            .map_err(js_error)?
+            .map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = removePolyfill)]
+    pub fn remove_polyfill(&mut self, anchor: i32) -> Result<(), JsError> {
+        self.stickfigure.remove_polyfill(DrawOrderIndex(anchor)).map_err(js_error)
+    }
+
+    /// Checks the stickfigure for problems the app would choke on, most severe first.
+    pub fn validate(&self) -> Result<JsDiagnosticArray, JsError> {
+        let diagnostics: Vec<DiagnosticValue> = self
+            .stickfigure
+            .validate()
+            .into_iter()
+            .map(|diagnostic| DiagnosticValue {
+                severity: alloc::format!("{:?}", diagnostic.severity),
+                draw_index: diagnostic.draw_index.map(|draw_index| draw_index.0),
+                message: diagnostic.message,
+            })
+            .collect();
+        to_js(&diagnostics)
+    }
+
+    /// Draws the stickfigure as an SVG document.
+    #[wasm_bindgen(js_name = toSvg)]
+    pub fn to_svg(&self, options: Option<JsSvgOptions>) -> Result<String, JsError> {
+        let value: SvgOptionsValue = match options {
+            Some(options) => from_js(options)?,
+            None => SvgOptionsValue::default(),
+        };
+        let defaults = SvgOptions::default();
+        let polyfill_fill_rule = match value.polyfill_fill_rule.as_deref() {
+            None => defaults.polyfill_fill_rule,
+            Some("EvenOdd") => FillRule::EvenOdd,
+            Some("NonZero") => FillRule::NonZero,
+            Some(other) => return Err(JsError::new(&alloc::format!("Unknown fill rule `{}`.", other))),
+        };
+        Ok(self.stickfigure.to_svg(SvgOptions {
+            padding: value.padding.unwrap_or(defaults.padding),
+            background: value.background,
+            width: value.width,
+            polyfill_fill_rule,
+        }))
+    }
+
+    /// Computes the global position, angle and scale of every node, in draw order.
+    #[wasm_bindgen(js_name = computePose)]
+    pub fn compute_pose(&self) -> Result<JsNodeTransformArray, JsError> {
+        let mut transforms: Vec<NodeTransformValue> = self
+            .stickfigure
+            .compute_pose()
+            .iter()
+            .map(|(draw_index, transform)| NodeTransformValue {
+                draw_index: draw_index.0,
+                start: point(transform.start),
+                end: point(transform.end),
+                angle: transform.angle,
+                scale: transform.scale,
+                thickness: transform.thickness,
+            })
+            .collect();
+        transforms.sort_by_key(|transform| transform.draw_index);
+        to_js(&transforms)
+    }
+
+    /// Gets the outlines and triangles of a node as currently posed, or `undefined` if there is no node at `drawIndex`.
+    #[wasm_bindgen(js_name = nodeShape)]
+    pub fn node_shape(&self, draw_index: i32) -> Result<Option<JsNodeShape>, JsError> {
+        let Some(node) = self.stickfigure.get_node(DrawOrderIndex(draw_index)) else {
+            return Ok(None);
+        };
+        let shape = node.shape(&self.stickfigure.compute_pose());
+        to_js(&NodeShapeValue {
+            contours: shape
+                .contours
+                .iter()
+                .map(|contour| contour.iter().copied().map(point).collect())
+                .collect(),
+            triangles: shape.triangles.iter().map(triangle).collect(),
+        })
+        .map(Some)
+    }
+
+    /// Gets the triangles and color of the polyfill anchored to a node, or `undefined` if there is none.
+    #[wasm_bindgen(js_name = polyfillMesh)]
+    pub fn polyfill_mesh(&self, anchor: i32) -> Result<Option<JsPolyfillMesh>, JsError> {
+        let Some(polyfill) = self.stickfigure.get_polyfill(DrawOrderIndex(anchor)) else {
+            return Ok(None);
+        };
+        let mesh = polyfill.get_mesh(&self.stickfigure.compute_pose(), FillRule::default(), &self.stickfigure);
+        to_js(&PolyfillMeshValue {
+            triangles: mesh.triangles.iter().map(triangle).collect(),
+            color: mesh.color,
+        })
+        .map(Some)
+    }
+
+    /// Gets the smallest box around everything drawn, or `undefined` if nothing is.
+    pub fn bounds(&self) -> Result<Option<JsBounds>, JsError> {
+        self.stickfigure
+            .bounds()
+            .map(|bounds| {
+                to_js(&BoundsValue {
+                    min: point(bounds.min),
+                    max: point(bounds.max),
+                })
+            })
+            .transpose()
+    }
+
+    /// Gets the topmost node drawn at, or within `tolerance` of, a point. Polyfills count as their anchor node.
+    #[wasm_bindgen(js_name = hitTest)]
+    pub fn hit_test(&self, x: f32, y: f32, tolerance: f32) -> Option<i32> {
+        self.stickfigure
+            .hit_test(Vec2::new(x, y), tolerance)
+            .map(|draw_index| draw_index.0)
+    }
+}
+
+impl Default for WasmStickfigure {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Stickfigure> for WasmStickfigure {
+    fn from(stickfigure: Stickfigure) -> Self {
+        Self { stickfigure }
+    }
+}
+
+impl From<WasmStickfigure> for Stickfigure {
+    fn from(wasm_stickfigure: WasmStickfigure) -> Self {
+        wasm_stickfigure.stickfigure
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::{format, vec::Vec};
+    use serde_json::{Map, Value};
+
+    /// Gets the fields of a TypeScript interface declared in `TYPESCRIPT_TYPES`, with whether each is optional.
+    fn declared_fields(interface: &str) -> Vec<(&'static str, bool)> {
+        let start = TYPESCRIPT_TYPES
+            .find(&format!("export interface {interface} "))
+            .unwrap_or_else(|| panic!("{interface} is not declared"));
+        let body = &TYPESCRIPT_TYPES[start..];
+        let body = &body[body.find('{').unwrap() + 1..body.find("\n}").unwrap()];
+
+        body.lines()
+            .filter_map(|line| line.trim().split_once(':'))
+            .map(|(name, _)| match name.strip_suffix('?') {
+                Some(name) => (name, true),
+                None => (name, false),
+            })
+            .collect()
+    }
+
+    /// Checks that `interface` declares every field of `value`, and that an object with only its required fields
+    /// deserializes as `T`.
+    fn assert_declares<T: Serialize + DeserializeOwned>(interface: &str, value: &T) {
+        let Value::Object(fields) = serde_json::to_value(value).unwrap() else {
+            panic!("{interface} does not serialize to an object");
+        };
+        let declared = declared_fields(interface);
+
+        for name in fields.keys() {
+            assert!(
+                declared.iter().any(|(declared_name, _)| declared_name == name),
+                "{interface} does not declare {name}"
+            );
+        }
+
+        let required: Map<String, Value> = fields
+            .into_iter()
+            .filter(|(name, _)| declared.contains(&(name.as_str(), false)))
+            .collect();
+        if let Err(error) = serde_json::from_value::<T>(Value::Object(required)) {
+            panic!("an object typed as {interface} does not deserialize: {error}");
+        }
+    }
+
+    #[test]
+    fn typescript_declarations_match_the_serde_form() {
+        assert_declares("Color", &Color::default());
+        assert_declares("ConnectorData", &ConnectorData::default());
+        assert_declares("NodeOptions", &NodeOptions::default());
+        assert_declares("Polyfill", &Polyfill::default());
+    }
+}
@@ -0,0 +1,166 @@
+//! Geometry of the stickfigures in `tests/fixtures`.
+//!
+//! The fixtures are written by this library, not exported by Stick Nodes. The expected coordinates are worked out by hand
+//! from the rules documented on `NodeTransform::from_parent` and `NodeTransform::from_parent_to_connector_end`, so they
+//! pin down this library's reading of those rules rather than confirm them against the app.
+//!
+//! The tests against the app read figures saved by Stick Nodes from `tests/fixtures/app`, each `<name>.nodes` next to a
+//! `<name>.ends` with one `<draw index> <x> <y>` line for the end point of every node as seen in the app (`#` starts a
+//! comment). They are ignored until those files are added.
+
+use glam::Vec2;
+use sticknodes_rs::{ConnectorData, ConnectorMethod, DrawOrderIndex, Stickfigure};
+
+fn read_fixture(name: &str) -> Stickfigure {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+    Stickfigure::from_bytes(std::fs::read(path).unwrap()).unwrap()
+}
+
+/// Reads `tests/fixtures/app/<name>.nodes` and the end points in `<name>.ends`.
+fn read_app_export(name: &str) -> (Stickfigure, Vec<(DrawOrderIndex, Vec2)>) {
+    let stickfigure = read_fixture(&format!("app/{name}.nodes"));
+    let path = format!("{}/tests/fixtures/app/{name}.ends", env!("CARGO_MANIFEST_DIR"));
+    let ends = std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| line.split('#').next().unwrap().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [draw_index, x, y] = fields[..] else {
+                panic!("expected `<draw index> <x> <y>`, got `{line}`");
+            };
+            (
+                DrawOrderIndex(draw_index.parse().unwrap()),
+                Vec2::new(x.parse().unwrap(), y.parse().unwrap()),
+            )
+        })
+        .collect();
+    (stickfigure, ends)
+}
+
+/// Asserts every end point in `ends` within `tolerance`, as the app shows coordinates rounded.
+fn assert_ends(stickfigure: &Stickfigure, ends: &[(DrawOrderIndex, Vec2)], tolerance: f32) {
+    assert!(!ends.is_empty(), "no end points to check");
+    for (draw_index, expected) in ends {
+        let actual = stickfigure.get_node(*draw_index).unwrap().get_global_end(stickfigure);
+        assert!(actual.distance(*expected) <= tolerance, "node {}: expected {expected}, got {actual}", draw_index.0);
+    }
+}
+
+fn assert_near(actual: Vec2, expected: Vec2) {
+    assert!(actual.distance(expected) < 1e-3, "expected {expected}, got {actual}");
+}
+
+/// `segment_scale.nodes` has a figure-wide scale of 2 and these nodes:
+///
+/// | draw index | parent | length | local angle | thickness | segment scale |
+/// |------------|--------|--------|-------------|-----------|---------------|
+/// | 1          | 0      | 10     | 0           | 4         | 1.5           |
+/// | 2          | 1      | 20     | 90          | 6         | not used      |
+/// | 3          | 2      | 10     | 90          | 8         | 0.5           |
+/// | 4          | 0      | 5      | 180         | 2         | not used      |
+///
+/// Node 3 is a trapezoid with a start thickness of 8 and an end thickness of 4.
+#[test]
+fn segment_scale_is_inherited_and_figure_scale_applies_to_every_node() {
+    let stickfigure = read_fixture("segment_scale.nodes");
+    let node = |draw_index| stickfigure.get_node(DrawOrderIndex(draw_index)).unwrap();
+
+    // Effective scale 2 * 1.5 = 3.
+    assert_near(node(1).get_global_end(&stickfigure), Vec2::new(30.0, 0.0));
+    assert_eq!(node(1).get_drawn_thickness(&stickfigure), 12);
+
+    // Inherits 3 without a segment scale of its own.
+    assert_near(node(2).get_global_start(&stickfigure), Vec2::new(30.0, 0.0));
+    assert_near(node(2).get_global_end(&stickfigure), Vec2::new(30.0, 60.0));
+    assert_eq!(node(2).get_drawn_thickness(&stickfigure), 18);
+
+    // 3 * 0.5 = 1.5, pointing back along the x axis.
+    assert_near(node(3).get_global_end(&stickfigure), Vec2::new(15.0, 60.0));
+    assert_eq!(node(3).get_drawn_thickness(&stickfigure), 12);
+    assert_eq!(node(3).get_trapezoid_thickness_start(&stickfigure), 12.0);
+    assert_eq!(node(3).get_trapezoid_thickness_end(&stickfigure), 6.0);
+
+    // Only the figure-wide scale applies.
+    assert_near(node(4).get_global_end(&stickfigure), Vec2::new(-10.0, 0.0));
+    assert_eq!(node(4).get_drawn_thickness(&stickfigure), 4);
+}
+
+/// `app/segment_scale.nodes` is a figure saved by Stick Nodes with a figure-wide scale other than 1, and nodes with and
+/// without a segment scale of their own below one that has it.
+#[test]
+#[ignore = "needs tests/fixtures/app/segment_scale.nodes and .ends exported from Stick Nodes"]
+fn segment_and_figure_scale_match_the_app() {
+    let (stickfigure, ends) = read_app_export("segment_scale");
+
+    assert_ne!(stickfigure.scale, 1.0, "the figure must be scaled");
+    let scaled = |draw_index: DrawOrderIndex| {
+        let node = stickfigure.get_node(draw_index).unwrap();
+        node.use_segment_scale && node.scale != 1.0
+    };
+    assert!(
+        stickfigure.get_all_node_indices().into_iter().any(|draw_index| {
+            scaled(draw_index) && stickfigure.get_children(draw_index).into_iter().any(|child| !scaled(child))
+        }),
+        "a node with a segment scale must have a child without one"
+    );
+
+    assert_ends(&stickfigure, &ends, 0.5);
+}
+
+/// `connectors.nodes` has these nodes, and three connectors from the end of node 2, at (30, 40), towards node 3:
+///
+/// | draw index | parent | length | local angle |
+/// |------------|--------|--------|-------------|
+/// | 1          | 0      | 30     | 0           |
+/// | 2          | 1      | 40     | 90          |
+/// | 3          | 0      | 10     | 90          |
+///
+/// Every connector has a `local_y` of -10, so it aims at the origin rather than the end of node 3, along a span of
+/// length 50.
+///
+/// | draw index | method    | percent | value | ancestral value | reversed |
+/// |------------|-----------|---------|-------|-----------------|----------|
+/// | 4          | ByPercent | 0.2     |       | 1               | no       |
+/// | 5          | ByValue   |         | 10    | 2               | no       |
+/// | 6          | ByValue   |         | 5     | 1               | yes      |
+#[test]
+fn connectors_end_along_the_span_to_their_end_node() {
+    let stickfigure = read_fixture("connectors.nodes");
+    let node = |draw_index| stickfigure.get_node(DrawOrderIndex(draw_index)).unwrap();
+
+    // A fifth of the span.
+    assert_near(node(4).get_global_start(&stickfigure), Vec2::new(30.0, 40.0));
+    assert_near(node(4).get_global_end(&stickfigure), Vec2::new(24.0, 32.0));
+
+    // 10 * 2 = 20 units along the span.
+    assert_near(node(5).get_global_end(&stickfigure), Vec2::new(18.0, 24.0));
+
+    // 5 units back from the origin.
+    assert_near(node(6).get_global_end(&stickfigure), Vec2::new(3.0, 4.0));
+}
+
+/// `app/connectors.nodes` is a figure saved by Stick Nodes with connectors placed by percent and by value, a reversed
+/// one, and one below a smart stretch node that has been stretched.
+#[test]
+#[ignore = "needs tests/fixtures/app/connectors.nodes and .ends exported from Stick Nodes"]
+fn connectors_match_the_app() {
+    let (stickfigure, ends) = read_app_export("connectors");
+
+    let connectors: Vec<&ConnectorData> = stickfigure
+        .get_all_node_indices()
+        .into_iter()
+        .filter_map(|draw_index| stickfigure.get_node(draw_index).unwrap().connector_data.as_ref())
+        .collect();
+    let has = |check: fn(&ConnectorData) -> bool| connectors.iter().any(|connector_data| check(connector_data));
+    assert!(has(|connector_data| connector_data.method == ConnectorMethod::ByPercent), "no connector by percent");
+    assert!(has(|connector_data| connector_data.method == ConnectorMethod::ByValue), "no connector by value");
+    assert!(has(|connector_data| connector_data.reversed), "no reversed connector");
+    assert!(
+        has(|connector_data| connector_data.smart_stretch_ancestral_value != 1.0),
+        "no connector moved by smart stretch"
+    );
+
+    assert_ends(&stickfigure, &ends, 0.5);
+}